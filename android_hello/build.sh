@@ -0,0 +1,17 @@
+#!/bin/sh
+# 使用 mini-apt 安装的 Android NDK 编译 hello.c
+#
+# 用法: build.sh <ndk-root> [api-level]
+#   例如: build.sh sysroot/android-ndk-r26b 21
+set -e
+
+NDK_ROOT=${1:?usage: build.sh <ndk-root> [api-level]}
+API_LEVEL=${2:-21}
+TOOLCHAIN="$NDK_ROOT/toolchains/llvm/prebuilt/linux-x86_64"
+
+"$TOOLCHAIN/bin/aarch64-linux-android${API_LEVEL}-clang" \
+    --sysroot="$TOOLCHAIN/sysroot" \
+    "$(dirname "$0")/hello.c" \
+    -o "$(dirname "$0")/hello"
+
+echo "Built $(dirname "$0")/hello"
@@ -24,6 +24,12 @@
 
 use std::path::PathBuf;
 
+use crate::package::architecture::Architecture;
+use crate::package::downloader::DownloadLimits;
+use crate::package::release::FreshnessPolicy;
+use crate::package::resolver::ResolveOptions;
+use crate::utils::http::HttpOptions;
+
 /// 包安装配置
 /// 
 /// 包含了安装软件包所需的所有配置信息。
@@ -33,10 +39,36 @@ pub struct InstallConfig {
     pub package_name: String,
     /// 镜像源列表，按优先级排序
     pub mirrors: Vec<String>,
-    /// 目标架构
-    pub architecture: String,
+    /// 目标（主）架构
+    pub architecture: Architecture,
+    /// 同时启用的其他架构，相当于 `dpkg --add-architecture`
+    pub foreign_architectures: Vec<Architecture>,
+    /// 发行版代号，对应镜像站的 `dists/<suite>` 目录
+    pub suite: String,
+    /// 使用的仓库组件
+    pub components: Vec<String>,
+    /// `Release` 文件的有效期检查
+    pub freshness: FreshnessPolicy,
     /// 安装根目录
     pub root_dir: PathBuf,
+    /// 全局最大并发下载数
+    pub max_concurrent_downloads: usize,
+    /// 每个主机的最大并发下载数
+    pub max_downloads_per_host: usize,
+    /// 代理、证书和超时等网络配置
+    pub http: HttpOptions,
+    /// 外部文件清单，清单中列出的名称由清单来源处理
+    pub manifests: Vec<PathBuf>,
+    /// 有多个包提供同一个虚包时优先选择的包，按优先顺序排列
+    pub preferred_providers: Vec<String>,
+    /// 是否同时安装依赖闭包中的所有包
+    pub resolve_dependencies: bool,
+    /// 解析依赖时是否同时安装 `Recommends` 中的包
+    pub install_recommends: bool,
+    /// 解析依赖时是否同时安装 `Suggests` 中的包
+    pub install_suggests: bool,
+    /// 只下载文件，不解包
+    pub download_only: bool,
 }
 
 impl Default for InstallConfig {
@@ -46,13 +78,40 @@ impl Default for InstallConfig {
     /// - package_name: 空字符串
     /// - mirrors: 空列表
     /// - architecture: "arm64"
+    /// - foreign_architectures: 空列表
+    /// - suite: "focal"
+    /// - components: ["main", "universe"]
+    /// - freshness: 检查 `Valid-Until`，不限制最长有效时间，允许 5 分钟的时钟误差
     /// - root_dir: "/"
+    /// - max_concurrent_downloads: 16
+    /// - max_downloads_per_host: 4
+    /// - http: 使用环境变量中的代理，不设置超时
+    /// - manifests: 空列表
+    /// - preferred_providers: 空列表
+    /// - resolve_dependencies: false
+    /// - install_recommends: false，sysroot 通常只需要必需的依赖
+    /// - install_suggests: false
+    /// - download_only: false
     fn default() -> Self {
+        let limits = DownloadLimits::default();
         Self {
             package_name: String::new(),
             mirrors: Vec::new(),
-            architecture: "arm64".to_string(),
+            architecture: Architecture::Arm64,
+            foreign_architectures: Vec::new(),
+            suite: "focal".to_string(),
+            components: vec!["main".to_string(), "universe".to_string()],
+            freshness: FreshnessPolicy::default(),
             root_dir: PathBuf::from("/"),
+            max_concurrent_downloads: limits.max_concurrent,
+            max_downloads_per_host: limits.max_per_host,
+            http: HttpOptions::default(),
+            manifests: Vec::new(),
+            preferred_providers: Vec::new(),
+            resolve_dependencies: false,
+            install_recommends: false,
+            install_suggests: false,
+            download_only: false,
         }
     }
 }
@@ -64,7 +123,7 @@ impl InstallConfig {
     /// 
     /// * `package_name` - 要安装的包名
     /// * `mirrors` - 镜像源列表
-    /// * `architecture` - 目标架构
+    /// * `architecture` - 目标架构，接受别名（如 `x86_64`、`aarch64`）和 GNU 三元组
     /// * `root_dir` - 安装根目录
     /// 
     /// # 返回值
@@ -74,42 +133,55 @@ impl InstallConfig {
     /// # 错误
     /// 
     /// 在以下情况会返回错误：
-    /// - 无效的架构名称
-    /// - 无效的镜像 URL
-    /// - 无效的目录路径
+    /// - 未知的架构名称（错误信息中会给出拼写建议）
     /// 
     /// # 示例
     /// 
     /// ```rust
     /// use mini_apt::config::InstallConfig;
+    /// use mini_apt::package::architecture::Architecture;
     /// use std::path::PathBuf;
     /// 
     /// let config = InstallConfig::new(
     ///     "example".to_string(),
     ///     vec!["https://mirrors.example.com".to_string()],
-    ///     "arm64".to_string(),
+    ///     "aarch64".to_string(),
     ///     PathBuf::from("/usr/local"),
     /// ).unwrap();
+    /// assert_eq!(config.architecture, Architecture::Arm64);
     /// ```
     pub fn new(package_name: String, mirrors: Vec<String>, architecture: String, root_dir: PathBuf) -> Result<Self, String> {
-        // 验证架构
-        let valid_architectures = vec![
-            "arm64", "x86_64", "all", "amd64", "i386",
-            "arm", "armhf", "arm64", "ppc64el", "s390x"
-        ];
-        
-        if !valid_architectures.contains(&architecture.as_str()) {
-            return Err(format!(
-                "Architecture must be one of: {}",
-                valid_architectures.join(", ")
-            ));
-        }
+        let architecture = architecture.parse::<Architecture>().map_err(|e| e.to_string())?;
 
         Ok(Self {
             package_name,
             mirrors,
             architecture,
             root_dir,
+            ..Self::default()
         })
     }
+
+    /// 所有启用的架构，主架构在前
+    pub fn architectures(&self) -> impl Iterator<Item = Architecture> + '_ {
+        std::iter::once(self.architecture).chain(self.foreign_architectures.iter().copied())
+    }
+
+    /// 依赖解析选项
+    pub fn resolve_options(&self) -> ResolveOptions {
+        ResolveOptions {
+            native_architecture: self.architecture.to_string(),
+            preferred_providers: self.preferred_providers.clone(),
+            install_recommends: self.install_recommends,
+            install_suggests: self.install_suggests,
+        }
+    }
+
+    /// 下载并发限制
+    pub fn download_limits(&self) -> DownloadLimits {
+        DownloadLimits {
+            max_concurrent: self.max_concurrent_downloads,
+            max_per_host: self.max_downloads_per_host,
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,176 @@
+//! # 错误类型模块
+//!
+//! 这个模块定义了库中使用的统一错误类型。
+//!
+//! 所有对外公开的操作都返回 [`Result`]，调用方可以根据 [`Error`] 的变体
+//! 区分网络错误、校验失败、包不存在等情况，而不必解析错误字符串。
+
+use std::path::PathBuf;
+
+/// 库中使用的结果类型
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// 文件系统错误
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// 访问特定路径时的文件系统错误
+    #[error("I/O error at {}: {source}", path.display())]
+    IoAt {
+        /// 出错的路径
+        path: PathBuf,
+        /// 原始错误
+        source: std::io::Error,
+    },
+
+    /// 网络请求错误
+    #[error("HTTP request to {url} failed: {message}")]
+    Http {
+        /// 请求的 URL
+        url: String,
+        /// 错误描述
+        message: String,
+    },
+
+    /// 服务器返回了非成功状态码
+    #[error("HTTP status {status} for {url}")]
+    Status {
+        /// 请求的 URL
+        url: String,
+        /// HTTP 状态码
+        status: u16,
+    },
+
+    /// 请求超时或传输停滞
+    #[error("Transfer from {url} timed out: {reason}")]
+    Timeout {
+        /// 请求的 URL
+        url: String,
+        /// 超时原因
+        reason: String,
+    },
+
+    /// 校验和不匹配
+    #[error("{algorithm} checksum mismatch for {file}. Expected: {expected}, got: {actual}")]
+    Checksum {
+        /// 校验算法名称
+        algorithm: &'static str,
+        /// 文件名或 URL
+        file: String,
+        /// 预期的校验和
+        expected: String,
+        /// 实际的校验和
+        actual: String,
+    },
+
+    /// 仓库中找不到请求的包
+    #[error("Package {name} not found in repository for architecture {architecture}{}", format_hint(suggestion))]
+    PackageNotFound {
+        /// 包名
+        name: String,
+        /// 目标架构
+        architecture: String,
+        /// 拼写建议或其他提示，例如 `did you mean hello?`
+        suggestion: Option<String>,
+    },
+
+    /// 请求的是有多个提供者的虚包，需要明确选择其中一个
+    #[error("{name} is a virtual package provided by {}, select one of them explicitly", providers.join(", "))]
+    VirtualPackage {
+        /// 虚包名
+        name: String,
+        /// 提供者的包名
+        providers: Vec<String>,
+    },
+
+    /// 无法满足的依赖
+    #[error("Unsatisfiable dependency: {package} depends on {dependency}{}", format_chain(chain))]
+    Unsatisfied {
+        /// 声明依赖的包
+        package: String,
+        /// 无法满足的依赖（包括所有候选项）
+        dependency: String,
+        /// 从请求的包到 `package` 的依赖链
+        chain: Vec<String>,
+    },
+
+    /// 依赖的每个候选包都因为冲突或者它自己的依赖而无法选择
+    #[error("Cannot satisfy {package} depends on {dependency}{}: {}", format_chain(chain), reasons.join("; "))]
+    Unresolvable {
+        /// 声明依赖的包
+        package: String,
+        /// 无法满足的依赖（包括所有候选项）
+        dependency: String,
+        /// 从请求的包到 `package` 的依赖链
+        chain: Vec<String>,
+        /// 每个候选包失败的原因
+        reasons: Vec<String>,
+    },
+
+    /// 两个包不能同时安装
+    #[error("{package} conflicts with {other}: {reason}")]
+    Conflict {
+        /// 要安装的包
+        package: String,
+        /// 与之冲突的包
+        other: String,
+        /// 冲突的原因
+        reason: String,
+    },
+
+    /// `Release` 文件无法解析、已过期、尚未生效、比缓存的版本旧，或者缓存过却无法下载
+    #[error("Release file {url} rejected: {reason}")]
+    InvalidRelease {
+        /// `Release` 文件的 URL
+        url: String,
+        /// 拒绝的原因
+        reason: String,
+    },
+
+    /// 所有镜像源都无法提供某个文件
+    #[error("No mirror could provide {0}")]
+    NoMirror(String),
+
+    /// 数据解析错误
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    /// 配置错误
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+}
+
+impl Error {
+    /// 根据 `reqwest` 错误构造网络错误
+    pub(crate) fn http(url: &str, err: impl std::fmt::Display) -> Self {
+        Error::Http {
+            url: url.to_string(),
+            message: err.to_string(),
+        }
+    }
+
+    /// 构造带路径的文件系统错误
+    pub(crate) fn io_at(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::IoAt {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+/// 错误信息中的提示，没有时为空
+fn format_hint(hint: &Option<String>) -> String {
+    hint.as_ref().map(|hint| format!(" ({})", hint)).unwrap_or_default()
+}
+
+/// 错误信息中的依赖链，只有请求的包本身时为空
+fn format_chain(chain: &[String]) -> String {
+    if chain.len() > 1 {
+        format!(", needed for {}", chain.join(" -> "))
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,522 @@
+//! # 安装器模块
+//!
+//! 这个模块提供了库的主要入口 [`Installer`]。与直接调用下载函数不同，
+//! 安装器不会向标准输出打印任何内容，而是返回一份详细的 [`InstallReport`]，
+//! 方便嵌入 mini-apt 的应用程序自行展示结果。
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::config::InstallConfig;
+//! use mini_apt::installer::Installer;
+//! use std::path::PathBuf;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = InstallConfig::new(
+//!         "cpp-x86-64-linux-gnu".to_string(),
+//!         vec!["https://mirrors.tuna.tsinghua.edu.cn/ubuntu-ports".to_string()],
+//!         "arm64".to_string(),
+//!         PathBuf::from("sysroot"),
+//!     )?;
+//!
+//!     let installer = Installer::builder(config).build()?;
+//!     let report = installer.install().await?;
+//!     for package in &report.packages {
+//!         println!("{} {} ({})", package.name, package.version, package.architecture);
+//!     }
+//!     println!("{} bytes transferred", report.bytes_transferred());
+//!     Ok(())
+//! }
+//! ```
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::InstallConfig;
+use crate::error::{Error, Result};
+use crate::package::downloader::DownloadManager;
+use crate::package::extended_states::{ExtendedStates, Mark};
+use crate::package::graph::DependencyGraph;
+use crate::package::index::PackageIndex;
+use crate::package::multiarch::effective_architecture;
+use crate::package::resolver::dependency_closure;
+use crate::package::status::StatusDb;
+use crate::progress::{format_bytes, ProgressObserver, SilentObserver};
+use crate::source::debian;
+use crate::source::manifest::{ArtifactManifest, ManifestSource};
+use crate::source::{Artifact, PackageSource, SourceContext, SourceRegistry};
+
+/// 已解析的软件包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackage {
+    /// 包名
+    pub name: String,
+    /// 版本号
+    pub version: String,
+    /// 架构
+    pub architecture: String,
+    /// 提供包索引的镜像源
+    pub mirror: String,
+    /// 安装后占用的空间（字节），未知时为 `None`
+    pub installed_size: Option<u64>,
+    /// 安装后记录到 [`ExtendedStates`] 的安装方式，`None` 表示不记录（升级已安装的包时
+    /// 保留原有的标记，不由 dpkg 管理的文件没有标记）
+    pub mark: Option<Mark>,
+}
+
+/// 已获取的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedFile {
+    /// 文件的下载 URL
+    pub url: String,
+    /// 实际提供该文件的镜像源
+    pub mirror: String,
+    /// 保存的本地路径
+    pub path: PathBuf,
+    /// 传输的字节数
+    pub bytes: u64,
+}
+
+/// 被跳过的项目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedItem {
+    /// 项目名称（包名或文件名）
+    pub name: String,
+    /// 跳过原因
+    pub reason: String,
+}
+
+/// 安装过程中发生的错误
+///
+/// `mirror` 为 `Some` 的错误只影响单个镜像源，例如某个镜像源不可用，但其他镜像源
+/// 成功提供了文件；`mirror` 为 `None` 的错误表示某个文件最终无法获取或解包。
+#[derive(Debug)]
+pub struct InstallError {
+    /// 出错的镜像源（如果与镜像源相关）
+    pub mirror: Option<String>,
+    /// 错误详情
+    pub error: Error,
+}
+
+/// 安装结果报告
+#[derive(Debug, Default)]
+pub struct InstallReport {
+    /// 已解析的软件包及其版本
+    pub packages: Vec<ResolvedPackage>,
+    /// 已下载的文件及其来源镜像
+    pub files: Vec<FetchedFile>,
+    /// 被跳过的项目
+    pub skipped: Vec<SkippedItem>,
+    /// 安装过程中遇到的错误
+    pub errors: Vec<InstallError>,
+}
+
+impl InstallReport {
+    /// 所有文件传输的总字节数
+    pub fn bytes_transferred(&self) -> u64 {
+        self.files.iter().map(|f| f.bytes).sum()
+    }
+
+    /// 所有请求的文件是否都已就绪
+    ///
+    /// 镜像源切换产生的错误不影响结果，只要最终每个文件都已获取（或被跳过）并解包。
+    pub fn is_complete(&self) -> bool {
+        self.errors.iter().all(|e| e.mirror.is_some())
+    }
+}
+
+/// 安装计划
+///
+/// 由 [`Installer::plan`] 生成，描述安装将要下载的内容，但不下载任何软件包文件。
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    /// 将要安装的软件包
+    pub packages: Vec<ResolvedPackage>,
+    /// 需要下载的文件
+    pub artifacts: Vec<Artifact>,
+    /// 无需处理的项目
+    pub skipped: Vec<SkippedItem>,
+    /// 解析过程中的非致命错误
+    pub errors: Vec<InstallError>,
+}
+
+impl InstallPlan {
+    /// 需要下载的总字节数（大小未知的文件不计入）
+    pub fn download_size(&self) -> u64 {
+        self.artifacts.iter().filter_map(|a| a.size).sum()
+    }
+
+    /// 安装后占用的总空间（字节，大小未知的包不计入）
+    pub fn installed_size(&self) -> u64 {
+        self.packages.iter().filter_map(|p| p.installed_size).sum()
+    }
+
+    /// 与 `apt-get --print-uris` 相同格式的下载列表
+    ///
+    /// 每个文件一行：`'URI' 文件名 大小 算法:校验和`，URI 取优先级最高的位置。
+    pub fn print_uris(&self) -> Vec<String> {
+        self.artifacts
+            .iter()
+            .filter_map(|artifact| {
+                let location = artifact.locations.first()?;
+                Some(format!(
+                    "'{}' {} {} {}",
+                    location.url,
+                    artifact.file_name,
+                    artifact.size.unwrap_or(0),
+                    artifact.checksum
+                ))
+            })
+            .collect()
+    }
+
+    /// `--dry-run` 输出的安装计划，每个元素为一行
+    ///
+    /// 依次列出将要安装的包、每个文件的下载位置（备用位置标为 `(fallback)`）、大小、
+    /// 校验和与保存路径、跳过的项目，以及下载量和占用空间的合计。
+    /// [`errors`](Self::errors) 不在其中，由调用者另行报告。
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec!["The following packages would be installed:".to_string()];
+        for package in &self.packages {
+            lines.push(format!(
+                "  {} {} ({}) from {}",
+                package.name, package.version, package.architecture, package.mirror
+            ));
+        }
+        if !self.artifacts.is_empty() {
+            lines.push("Downloads:".to_string());
+        }
+        for artifact in &self.artifacts {
+            for (index, location) in artifact.locations.iter().enumerate() {
+                let marker = if index == 0 { "" } else { "  (fallback) " };
+                lines.push(format!("  {}{}", marker, location.url));
+            }
+            let size = artifact.size.map(format_bytes).unwrap_or_else(|| "unknown size".to_string());
+            lines.push(format!("    {} {} -> {}", size, artifact.checksum, artifact.path().display()));
+        }
+        for item in &self.skipped {
+            lines.push(format!("Skip {}: {}", item.name, item.reason));
+        }
+        lines.push(format!("Need to get {} of archives.", format_bytes(self.download_size())));
+        lines.push(format!(
+            "After this operation, {} of additional disk space will be used.",
+            format_bytes(self.installed_size())
+        ));
+        lines
+    }
+}
+
+/// 安装器构建器
+///
+/// 以 [`InstallConfig`] 为基础，可以在构建前覆盖部分配置。
+#[derive(Clone)]
+pub struct InstallerBuilder {
+    config: InstallConfig,
+    observer: Arc<dyn ProgressObserver>,
+    sources: SourceRegistry,
+}
+
+impl InstallerBuilder {
+    /// 追加一个镜像源（优先级最低）
+    pub fn mirror(mut self, mirror: impl Into<String>) -> Self {
+        self.config.mirrors.push(mirror.into());
+        self
+    }
+
+    /// 设置安装根目录
+    pub fn root_dir(mut self, root_dir: impl Into<PathBuf>) -> Self {
+        self.config.root_dir = root_dir.into();
+        self
+    }
+
+    /// 设置进度观察者
+    ///
+    /// 默认使用 [`SilentObserver`]，不输出任何进度信息。
+    pub fn observer(mut self, observer: Arc<dyn ProgressObserver>) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// 注册额外的软件包来源
+    ///
+    /// 后注册的来源优先级更高，Debian 仓库始终作为最后的后备来源。
+    pub fn source(mut self, source: Arc<dyn PackageSource>) -> Self {
+        self.sources.register(source);
+        self
+    }
+
+    /// 构建安装器
+    ///
+    /// # 错误
+    ///
+    /// 未配置包名时返回 [`Error::Config`]，配置的清单文件无法读取或解析时返回对应的错误
+    pub fn build(mut self) -> Result<Installer> {
+        if self.config.package_name.is_empty() {
+            return Err(Error::Config("package name is empty".to_string()));
+        }
+        for path in &self.config.manifests {
+            let manifest = ArtifactManifest::load(path)?;
+            self.sources.register(Arc::new(ManifestSource::new(manifest)));
+        }
+        let downloader = Arc::new(DownloadManager::with_options(self.config.download_limits(), &self.config.http)?);
+        Ok(Installer {
+            config: self.config,
+            observer: self.observer,
+            downloader,
+            sources: self.sources,
+        })
+    }
+}
+
+/// 软件包安装器
+#[derive(Clone)]
+pub struct Installer {
+    config: InstallConfig,
+    observer: Arc<dyn ProgressObserver>,
+    downloader: Arc<DownloadManager>,
+    sources: SourceRegistry,
+}
+
+impl Installer {
+    /// 从安装配置创建构建器
+    pub fn builder(config: InstallConfig) -> InstallerBuilder {
+        InstallerBuilder {
+            config,
+            observer: Arc::new(SilentObserver),
+            sources: SourceRegistry::default(),
+        }
+    }
+
+    /// 当前使用的配置
+    pub fn config(&self) -> &InstallConfig {
+        &self.config
+    }
+
+    /// 执行安装并返回结果报告
+    ///
+    /// # 错误
+    ///
+    /// 只有在无法得到任何安装计划时才返回错误，例如所有镜像源的包索引都无法下载，
+    /// 或者请求的包不存在。单个镜像源的失败会记录在 [`InstallReport::errors`] 中。
+    pub async fn install(&self) -> Result<InstallReport> {
+        let source = self.source()?;
+        let ctx = self.context();
+
+        let resolution = source.resolve(&ctx, &self.config.package_name).await?;
+        let mut report = InstallReport {
+            packages: resolution.packages,
+            skipped: resolution.skipped,
+            errors: resolution.errors,
+            ..InstallReport::default()
+        };
+
+        // 并行下载，全部就绪后再逐个解包和记录，避免多个包同时写同一个文件
+        let outcomes = futures::future::join_all(
+            resolution.artifacts.iter().map(|artifact| self.fetch_artifact(source.as_ref(), &ctx, artifact)),
+        ).await;
+        // 任何文件无法获取时都不解包，避免依赖它的包被解包并记录为已安装
+        let mut failed = outcomes.iter().find(|outcome| !outcome.ready).map(|_| "a download failed".to_string());
+        let before = StatusDb::load(&self.config.root_dir)?;
+        let mut outcomes: Vec<_> = resolution.artifacts.iter().zip(outcomes).collect();
+        // 解析结果中依赖排在依赖它的包之后，倒序解包使依赖先于依赖它的包解包
+        for (artifact, outcome) in outcomes.iter_mut().rev() {
+            if self.config.download_only {
+                break;
+            }
+            if let Some(reason) = &failed {
+                if outcome.ready {
+                    outcome.skipped = Some(SkippedItem {
+                        name: artifact.file_name.clone(),
+                        reason: format!("not unpacked because {}", reason),
+                    });
+                }
+                continue;
+            }
+            if let Err(error) = source.unpack(&ctx, artifact, &artifact.path()).await {
+                outcome.errors.push(InstallError { mirror: None, error });
+                failed = Some(format!("unpacking {} failed", artifact.file_name));
+            }
+        }
+        for (_, outcome) in outcomes {
+            report.files.extend(outcome.file);
+            report.skipped.extend(outcome.skipped);
+            report.errors.extend(outcome.errors);
+        }
+        if !self.config.download_only {
+            // 有文件没有解包时，之前已经是同一版本的包可能正是解包失败的包，不改变它的标记
+            let unchanged = failed.is_some().then_some(&before);
+            if let Err(error) = self.record_marks(&report.packages, unchanged) {
+                report.errors.push(InstallError { mirror: None, error });
+            }
+        }
+        Ok(report)
+    }
+
+    /// 只解析请求，生成安装计划
+    ///
+    /// 会下载包索引，但不下载软件包文件，也不修改安装根目录中的内容。
+    ///
+    /// # 错误
+    ///
+    /// 与 [`Installer::install`] 相同，无法得到任何安装计划时返回错误
+    pub async fn plan(&self) -> Result<InstallPlan> {
+        let source = self.source()?;
+        let resolution = source.resolve(&self.query_context(), &self.config.package_name).await?;
+        Ok(InstallPlan {
+            packages: resolution.packages,
+            artifacts: resolution.artifacts,
+            skipped: resolution.skipped,
+            errors: resolution.errors,
+        })
+    }
+
+    /// 下载包索引，使用第一个能提供索引的镜像源
+    ///
+    /// # 错误
+    ///
+    /// 没有配置镜像源时返回 [`Error::Config`]，所有镜像源都无法提供索引时返回
+    /// [`Error::NoMirror`]
+    pub async fn package_index(&self) -> Result<PackageIndex> {
+        if self.config.mirrors.is_empty() {
+            return Err(Error::Config("no mirrors configured".to_string()));
+        }
+        let ctx = self.query_context();
+        for mirror in &self.config.mirrors {
+            if let Ok(packages) = debian::fetch_index(&ctx, mirror).await {
+                return Ok(packages);
+            }
+        }
+        Err(Error::NoMirror("package index".to_string()))
+    }
+
+    /// 安装根目录中已安装的包，只下载时为空
+    ///
+    /// # 错误
+    ///
+    /// 状态数据库无法读取时返回错误
+    pub fn installed_packages(&self) -> Result<PackageIndex> {
+        debian::installed_packages(&self.query_context())
+    }
+
+    /// 解析请求的包，返回依赖闭包的依赖图
+    ///
+    /// 与 [`Installer::plan`] 使用相同的解析规则，但包括已安装的包，用于审计依赖关系。
+    ///
+    /// # 错误
+    ///
+    /// 包索引无法下载、请求的包不存在或依赖无法满足时返回错误
+    pub async fn dependency_graph(&self) -> Result<DependencyGraph> {
+        let (name, arch) = debian::requested_architecture(&self.config, &self.config.package_name)?;
+        let packages = self.package_index().await?;
+        let installed = self.installed_packages()?;
+        let root = debian::find_requested(&self.config, &packages, name, arch)?.ok_or_else(|| {
+            Error::PackageNotFound {
+                name: name.to_string(),
+                architecture: arch.to_string(),
+                suggestion: debian::suggest(&packages, name),
+            }
+        })?;
+        let selected = dependency_closure(&packages, root, &installed, &self.config.resolve_options())?;
+        Ok(DependencyGraph::build(&selected, &installed, self.config.architecture.as_str()))
+    }
+
+    /// 把成功安装的包的安装方式记录到 [`ExtendedStates`]
+    ///
+    /// 只记录已安装的版本与解析结果相同的包；给出 `unchanged`（解包之前的状态数据库）时，
+    /// 之前已经安装了这个版本的包也不记录。
+    fn record_marks(&self, packages: &[ResolvedPackage], unchanged: Option<&StatusDb>) -> Result<()> {
+        let marked: Vec<(&ResolvedPackage, Mark)> =
+            packages.iter().filter_map(|package| package.mark.map(|mark| (package, mark))).collect();
+        if marked.is_empty() {
+            return Ok(());
+        }
+        let root_dir = &self.config.root_dir;
+        let db = StatusDb::load(root_dir)?;
+        let native = self.config.architecture.as_str();
+        ExtendedStates::update(root_dir, |states| {
+            for (package, mark) in marked {
+                let name = format!("{}:{}", package.name, package.architecture);
+                let version = Some(package.version.as_str());
+                let unpacked = unchanged.is_none_or(|before| before.installed_version(&name) != version);
+                if db.installed_version(&name) == version && unpacked {
+                    states.set(&package.name, effective_architecture(&package.architecture, native), mark);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn source(&self) -> Result<&Arc<dyn PackageSource>> {
+        let request = self.config.package_name.as_str();
+        self.sources
+            .find(request)
+            .ok_or_else(|| Error::Config(format!("no package source handles {}", request)))
+    }
+
+    fn context(&self) -> SourceContext<'_> {
+        SourceContext {
+            config: &self.config,
+            downloader: &self.downloader,
+            observer: self.observer.as_ref(),
+            read_only: false,
+        }
+    }
+
+    /// 不修改安装根目录的上下文，用于安装计划和查询
+    fn query_context(&self) -> SourceContext<'_> {
+        SourceContext { read_only: true, ..self.context() }
+    }
+
+    /// 获取单个文件
+    ///
+    /// 本地文件（没有下载位置）直接校验；本地已有通过校验的文件时跳过下载；
+    /// 否则依次尝试各个候选位置。
+    async fn fetch_artifact(&self, source: &dyn PackageSource, ctx: &SourceContext<'_>, artifact: &Artifact) -> ArtifactOutcome {
+        let mut outcome = ArtifactOutcome::default();
+        let path = artifact.path();
+
+        if artifact.locations.is_empty() {
+            if let Err(error) = source.verify(ctx, artifact, &path) {
+                outcome.errors.push(InstallError { mirror: None, error });
+                return outcome;
+            }
+        } else if path.is_file() && source.verify(ctx, artifact, &path).is_ok() {
+            outcome.skipped = Some(SkippedItem {
+                name: artifact.file_name.clone(),
+                reason: "already downloaded and verified".to_string(),
+            });
+        } else {
+            for location in &artifact.locations {
+                match source.fetch(ctx, artifact, location).await {
+                    Ok(file) => {
+                        outcome.file = Some(FetchedFile {
+                            url: file.url,
+                            mirror: location.mirror.clone(),
+                            path: file.path,
+                            bytes: file.bytes,
+                        });
+                        break;
+                    }
+                    Err(error) => outcome.errors.push(InstallError { mirror: Some(location.mirror.clone()), error }),
+                }
+            }
+            if outcome.file.is_none() {
+                outcome.errors.push(InstallError { mirror: None, error: Error::NoMirror(artifact.file_name.clone()) });
+                return outcome;
+            }
+        }
+        outcome.ready = true;
+        outcome
+    }
+}
+
+/// 单个文件的处理结果
+#[derive(Default)]
+struct ArtifactOutcome {
+    file: Option<FetchedFile>,
+    skipped: Option<SkippedItem>,
+    errors: Vec<InstallError>,
+    /// 文件已经就绪，可以解包
+    ready: bool,
+}
@@ -2,12 +2,13 @@
 //! 
 //! 一个用 Rust 编写的简化版 APT 包管理器。
 //! 
-//! 这个库提供了从 Ubuntu 镜像站下载和安装软件包的功能，支持异步下载、MD5 校验和多镜像源。
+//! 这个库提供了从 Ubuntu 镜像站下载和安装软件包的功能，支持异步下载、SHA256 校验和多镜像源。
 //! 
 //! ## 主要功能
 //! 
 //! - 异步并行下载软件包
-//! - MD5 校验和验证
+//! - SHA256 校验和验证
+//! - 依赖解析与 dpkg 格式的安装状态记录
 //! - 支持多镜像源
 //! - 支持 main 和 universe 仓库
 //! 
@@ -15,11 +16,11 @@
 //! 
 //! ```rust,no_run
 //! use mini_apt::config::InstallConfig;
-//! use mini_apt::utils::url::UrlBuilder;
+//! use mini_apt::installer::Installer;
 //! use std::path::PathBuf;
 //! 
 //! #[tokio::main]
-//! async fn main() -> Result<(), String> {
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let config = InstallConfig::new(
 //!         "cpp-x86-64-linux-gnu".to_string(),
 //!         vec!["https://mirrors.tuna.tsinghua.edu.cn/ubuntu-ports".to_string()],
@@ -27,15 +28,16 @@
 //!         PathBuf::from("sysroot"),
 //!     )?;
 //! 
-//!     for mirror in &config.mirrors {
-//!         if UrlBuilder::build_package_urls(&config, mirror).await {
-//!             break;
-//!         }
-//!     }
+//!     let report = Installer::builder(config).build()?.install().await?;
+//!     println!("Downloaded {} bytes", report.bytes_transferred());
 //!     Ok(())
 //! }
 //! ```
 
 pub mod config;
+pub mod error;
+pub mod installer;
 pub mod package;
+pub mod progress;
+pub mod source;
 pub mod utils; 
\ No newline at end of file
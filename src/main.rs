@@ -1,121 +1,767 @@
 #![deny(warnings)]
 
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Arc;
+use std::time::Duration;
 
-mod config;
-mod package;
-mod utils;
-
-use config::InstallConfig;
-use utils::url::UrlBuilder;
+use mini_apt::config::InstallConfig;
+use mini_apt::error::Error;
+use mini_apt::installer::{InstallPlan, InstallReport, Installer};
+use mini_apt::package::architecture::Architecture;
+use mini_apt::package::deb::DebArchive;
+use mini_apt::package::explain::{autoremovable, manual_packages, top_level, why};
+use mini_apt::package::extended_states::{ExtendedStates, Mark};
+use mini_apt::package::graph::reverse_dependencies;
+use mini_apt::package::index::PackageIndex;
+use mini_apt::package::multiarch::{effective_architecture, split_qualified_name};
+use mini_apt::package::release::FreshnessPolicy;
+use mini_apt::package::status::StatusDb;
+use mini_apt::package::PackageInfo;
+use mini_apt::progress::TerminalProgress;
+use mini_apt::source::ndk::{NdkInstallation, NdkRelease};
+use mini_apt::utils::auth::AuthConf;
+use mini_apt::utils::http::{HttpOptions, ProxySettings, StallDetection};
 
 fn print_usage() {
     println!("Usage: mini-apt <command> [options]");
     println!();
     println!("Commands:");
     println!("  install    Install a package");
+    println!("  download   Download verified .deb files without extracting");
+    println!("  info       Show control fields and maintainer scripts of a .deb file");
+    println!("  contents   List the files in a .deb file");
+    println!("  why        Show which installed package requires a package");
+    println!("  why-not    Explain why a package is not installed or cannot be installed");
+    println!("  depends    Show the relations of a package");
+    println!("  rdepends   Show the packages that depend on a package");
+    println!("  graph      Export the resolved dependency graph");
+    println!("  autoremove Remove automatically installed packages that are no longer needed");
+    println!("  mark       Mark a package as automatically or manually installed (mark auto|manual <package>)");
     println!("  echo      Echo back the input text");
     println!("  help      Show this help message");
     println!();
-    println!("Options for install:");
+    println!("Options for install, download, why, why-not, depends, rdepends, graph, autoremove and mark:");
     println!("  -u, --url <url>          Mirror URL");
     println!("  -m, --arch <arch>        Architecture");
-    println!("  -d, --dir <dir>          Root directory");
-    println!("  <package>                Package name");
+    println!("      --foreign-arch <arch>  Additional architecture for Multi-Arch packages (repeatable)");
+    println!("      --suite <suite>      Distribution suite (default: focal)");
+    println!("      --components <list>  Comma-separated components (default: main,universe)");
+    println!("      --no-check-valid-until  Accept Release files past their Valid-Until date");
+    println!("      --max-release-age <secs>  Reject Release files older than this (from Date)");
+    println!("      --clock-skew <secs>  Tolerated clock difference to mirrors (default: 300)");
+    println!("  -d, --dir <dir>          Root directory (download: target directory, default .)");
+    println!("  -j, --jobs <n>           Maximum concurrent downloads");
+    println!("      --max-per-host <n>   Maximum concurrent downloads per host");
+    println!("      --proxy <url>        HTTP/HTTPS proxy (default: http_proxy/https_proxy)");
+    println!("      --no-proxy <hosts>   Comma-separated hosts that bypass the proxy");
+    println!("      --ca-cert <file>     Additional trusted CA certificates (PEM)");
+    println!("      --connect-timeout <secs>  Connection timeout");
+    println!("      --read-timeout <secs>     Timeout waiting for data");
+    println!("      --low-speed-limit <bytes/s>  Abort transfers slower than this for 30s");
+    println!("      --auth-conf <file>   apt auth.conf credentials (default: /etc/apt/auth.conf{{,.d}})");
+    println!("      --manifest <file>    External artifact manifest (repeatable)");
+    println!("      --dry-run            Resolve and print the install plan without downloading");
+    println!("      --print-uris         Print download URIs in apt-get --print-uris format");
+    println!("      --prefer <package>   Preferred provider of virtual packages (repeatable)");
+    println!("      --install-recommends  Also install recommended packages when possible");
+    println!("      --no-install-recommends  Do not install recommended packages (default)");
+    println!("      --install-suggests   Also install suggested packages when possible");
+    println!("      --with-deps          Also fetch dependencies (default for install)");
+    println!("      --no-deps            Only fetch the requested package (default for download)");
+    println!("      --format <dot|json>  Output format of graph (default: dot)");
+    println!("  <package>                Package name, or a local .deb file for install");
 }
 
-#[tokio::main]
-async fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() < 2 {
-        print_usage();
+/// 读取选项后面的参数值
+fn option_value<'a>(args: &'a [String], i: usize, option: &str) -> &'a str {
+    match args.get(i + 1) {
+        Some(value) => value,
+        None => {
+            eprintln!("Error: Missing value for {}", option);
+            process::exit(1);
+        }
+    }
+}
+
+/// 解析选项后面的正整数参数
+fn parse_count(args: &[String], i: usize, option: &str) -> usize {
+    match args.get(i + 1).map(|v| v.parse::<usize>()) {
+        Some(Ok(n)) if n > 0 => n,
+        Some(_) => {
+            eprintln!("Error: Invalid value for {}: {}", option, args[i + 1]);
+            process::exit(1);
+        }
+        None => {
+            eprintln!("Error: Missing value for {}", option);
+            process::exit(1);
+        }
+    }
+}
+
+/// 读取认证凭据文件，未指定时使用 APT 的默认位置
+///
+/// 明确指定的文件无法读取时退出；默认文件无法读取时（例如普通用户运行）只输出警告。
+fn load_auth(files: &[PathBuf]) -> AuthConf {
+    if files.is_empty() {
+        let (conf, warnings) = AuthConf::load_apt_defaults(Path::new("/etc/apt"));
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        return conf;
+    }
+    let result: mini_apt::error::Result<AuthConf> = files.iter().try_fold(AuthConf::default(), |mut conf, file| {
+        conf.extend(AuthConf::load(file)?);
+        Ok(conf)
+    });
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
         process::exit(1);
+    })
+}
+
+/// 安装 NDK 后输出工具链和 sysroot 的路径
+fn print_ndk_paths(config: &InstallConfig) {
+    if !config.package_name.starts_with("android-ndk") {
+        return;
+    }
+    let ndk = NdkRelease::from_package_name(&config.package_name)
+        .ok()
+        .and_then(|release| NdkInstallation::locate(&config.root_dir, &release));
+    if let Some(ndk) = ndk {
+        println!("NDK root:  {}", ndk.root.display());
+        println!("Toolchain: {}", ndk.toolchain_dir().display());
+        println!("Sysroot:   {}", ndk.sysroot().display());
+        println!("Compiler:  {}", ndk.clang("aarch64-linux-android", 21).display());
     }
+}
 
-    match args[1].as_str() {
-        "install" => {
-            let mut i = 2;
-            let mut mirror_url = String::new();
-            let mut architecture = String::new();
-            let mut root_dir = PathBuf::new();
-            let mut package_name = String::new();
-
-            while i < args.len() {
-                match args[i].as_str() {
-                    "-u" | "--url" => {
-                        if i + 1 < args.len() {
-                            mirror_url = args[i + 1].clone();
-                            i += 2;
-                        } else {
-                            eprintln!("Error: Missing value for --url");
-                            process::exit(1);
-                        }
-                    }
-                    "-m" | "--arch" => {
-                        if i + 1 < args.len() {
-                            architecture = args[i + 1].clone();
-                            i += 2;
-                        } else {
-                            eprintln!("Error: Missing value for --arch");
-                            process::exit(1);
-                        }
-                    }
-                    "-d" | "--dir" => {
-                        if i + 1 < args.len() {
-                            root_dir = PathBuf::from(&args[i + 1]);
-                            i += 2;
-                        } else {
-                            eprintln!("Error: Missing value for --dir");
-                            process::exit(1);
-                        }
-                    }
-                    _ => {
-                        if package_name.is_empty() {
-                            package_name = args[i].clone();
-                            i += 1;
-                        } else {
-                            eprintln!("Error: Unexpected argument: {}", args[i]);
-                            process::exit(1);
-                        }
+/// 输出安装计划
+fn print_plan(plan: &InstallPlan) {
+    for error in &plan.errors {
+        match &error.mirror {
+            Some(mirror) => eprintln!("Warning: {} (mirror {})", error.error, mirror),
+            None => eprintln!("Warning: {}", error.error),
+        }
+    }
+    plan.summary().iter().for_each(|line| println!("{}", line));
+}
+
+fn print_report(report: &InstallReport) {
+    for package in &report.packages {
+        println!("Found package: {} version {} ({})", package.name, package.version, package.architecture);
+    }
+    for file in &report.files {
+        println!("Downloaded {} from {} ({} bytes)", file.path.display(), file.mirror, file.bytes);
+    }
+    for item in &report.skipped {
+        println!("Skipped {}: {}", item.name, item.reason);
+    }
+    for error in &report.errors {
+        match &error.mirror {
+            Some(mirror) => eprintln!("Error: {} (mirror {})", error.error, mirror),
+            None => eprintln!("Error: {}", error.error),
+        }
+    }
+    println!("Total downloaded: {} bytes", report.bytes_transferred());
+}
+
+/// `install` 和 `download` 共用的命令行选项
+struct CommandOptions {
+    config: InstallConfig,
+    dry_run: bool,
+    print_uris: bool,
+    /// `graph` 的输出格式
+    format: GraphFormat,
+}
+
+/// `graph` 的输出格式
+#[derive(Clone, Copy)]
+enum GraphFormat {
+    Dot,
+    Json,
+}
+
+/// 解析命令名之后的选项
+///
+/// `install` 必须指定 `--dir`，默认安装依赖；`download` 默认保存到当前目录，
+/// 默认不下载依赖。
+fn parse_command_options(args: &[String], download_only: bool) -> CommandOptions {
+    parse_options(&args[2..], download_only, true)
+}
+
+/// 解析选项，`args` 从第一个选项开始；`package_required` 为 `false` 时可以不指定包名
+fn parse_options(args: &[String], download_only: bool, package_required: bool) -> CommandOptions {
+    let mut i = 0;
+    let mut mirror_url = String::new();
+    let mut architecture = String::new();
+    let mut foreign_architectures: Vec<Architecture> = Vec::new();
+    let mut root_dir = if download_only { PathBuf::from(".") } else { PathBuf::new() };
+    let mut package_name = String::new();
+    let mut jobs: Option<usize> = None;
+    let mut max_per_host: Option<usize> = None;
+    let mut http = HttpOptions::default();
+    let mut auth_files: Vec<PathBuf> = Vec::new();
+    let mut manifests: Vec<PathBuf> = Vec::new();
+    let mut suite: Option<String> = None;
+    let mut preferred_providers: Vec<String> = Vec::new();
+    let mut freshness = FreshnessPolicy::default();
+    let mut components: Option<Vec<String>> = None;
+    let mut dry_run = false;
+    let mut print_uris = false;
+    let mut format = GraphFormat::Dot;
+    let mut with_deps = !download_only;
+    let mut install_recommends = false;
+    let mut install_suggests = false;
+
+    while i < args.len() {
+        match args[i].as_str() {
+            "-u" | "--url" => {
+                mirror_url = option_value(args, i, "--url").to_string();
+                i += 2;
+            }
+            "-m" | "--arch" => {
+                architecture = option_value(args, i, "--arch").to_string();
+                i += 2;
+            }
+            "--foreign-arch" => {
+                let arch = option_value(args, i, "--foreign-arch").parse().unwrap_or_else(|e| {
+                    eprintln!("Error: {}", e);
+                    process::exit(1);
+                });
+                foreign_architectures.push(arch);
+                i += 2;
+            }
+            "-d" | "--dir" => {
+                root_dir = PathBuf::from(option_value(args, i, "--dir"));
+                i += 2;
+            }
+            "-j" | "--jobs" => {
+                jobs = Some(parse_count(args, i, "--jobs"));
+                i += 2;
+            }
+            "--max-per-host" => {
+                max_per_host = Some(parse_count(args, i, "--max-per-host"));
+                i += 2;
+            }
+            "--proxy" => {
+                let proxy = http.proxy.get_or_insert_with(ProxySettings::from_env);
+                let url = option_value(args, i, "--proxy").to_string();
+                proxy.http = Some(url.clone());
+                proxy.https = Some(url);
+                i += 2;
+            }
+            "--no-proxy" => {
+                let proxy = http.proxy.get_or_insert_with(ProxySettings::from_env);
+                proxy.no_proxy = option_value(args, i, "--no-proxy")
+                    .split(',')
+                    .map(|h| h.trim().to_string())
+                    .filter(|h| !h.is_empty())
+                    .collect();
+                i += 2;
+            }
+            "--ca-cert" => {
+                http.ca_certificates.push(PathBuf::from(option_value(args, i, "--ca-cert")));
+                i += 2;
+            }
+            "--auth-conf" => {
+                auth_files.push(PathBuf::from(option_value(args, i, "--auth-conf")));
+                i += 2;
+            }
+            "--dry-run" | "--simulate" | "-s" => {
+                dry_run = true;
+                i += 1;
+            }
+            "--print-uris" => {
+                print_uris = true;
+                i += 1;
+            }
+            "--with-deps" => {
+                with_deps = true;
+                i += 1;
+            }
+            "--no-deps" => {
+                with_deps = false;
+                i += 1;
+            }
+            "--format" => {
+                format = match option_value(args, i, "--format") {
+                    "dot" => GraphFormat::Dot,
+                    "json" => GraphFormat::Json,
+                    other => {
+                        eprintln!("Error: Invalid value for --format: {} (expected dot or json)", other);
+                        process::exit(1);
                     }
+                };
+                i += 2;
+            }
+            "--install-recommends" => {
+                install_recommends = true;
+                i += 1;
+            }
+            "--no-install-recommends" => {
+                install_recommends = false;
+                i += 1;
+            }
+            "--install-suggests" => {
+                install_suggests = true;
+                i += 1;
+            }
+            "--prefer" => {
+                preferred_providers.push(option_value(args, i, "--prefer").to_string());
+                i += 2;
+            }
+            "--suite" => {
+                suite = Some(option_value(args, i, "--suite").to_string());
+                i += 2;
+            }
+            "--components" => {
+                components = Some(
+                    option_value(args, i, "--components")
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string)
+                        .collect(),
+                );
+                i += 2;
+            }
+            "--no-check-valid-until" => {
+                freshness.check_valid_until = false;
+                i += 1;
+            }
+            "--max-release-age" => {
+                freshness.max_age = Some(Duration::from_secs(parse_count(args, i, "--max-release-age") as u64));
+                i += 2;
+            }
+            "--clock-skew" => {
+                freshness.clock_skew = Duration::from_secs(parse_count(args, i, "--clock-skew") as u64);
+                i += 2;
+            }
+            "--manifest" => {
+                manifests.push(PathBuf::from(option_value(args, i, "--manifest")));
+                i += 2;
+            }
+            "--connect-timeout" => {
+                http.connect_timeout = Some(Duration::from_secs(parse_count(args, i, "--connect-timeout") as u64));
+                i += 2;
+            }
+            "--read-timeout" => {
+                http.read_timeout = Some(Duration::from_secs(parse_count(args, i, "--read-timeout") as u64));
+                i += 2;
+            }
+            "--low-speed-limit" => {
+                http.stall = Some(StallDetection {
+                    min_bytes_per_sec: parse_count(args, i, "--low-speed-limit") as u64,
+                    window: Duration::from_secs(30),
+                });
+                i += 2;
+            }
+            _ => {
+                if package_name.is_empty() {
+                    package_name = args[i].clone();
+                    i += 1;
+                } else {
+                    eprintln!("Error: Unexpected argument: {}", args[i]);
+                    process::exit(1);
                 }
             }
+        }
+    }
+
+    if architecture.is_empty() || root_dir.as_os_str().is_empty() || (package_required && package_name.is_empty()) {
+        eprintln!("Error: Missing required arguments");
+        print_usage();
+        process::exit(1);
+    }
+
+    let mut config = InstallConfig::new(
+        package_name,
+        if mirror_url.is_empty() { Vec::new() } else { vec![mirror_url] },
+        architecture,
+        root_dir,
+    ).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    if let Some(jobs) = jobs {
+        config.max_concurrent_downloads = jobs;
+    }
+    if let Some(max_per_host) = max_per_host {
+        config.max_downloads_per_host = max_per_host;
+    }
+    if let Some(suite) = suite {
+        config.suite = suite;
+    }
+    if let Some(components) = components {
+        config.components = components;
+    }
+    config.foreign_architectures = foreign_architectures;
+    config.freshness = freshness;
+    http.auth = load_auth(&auth_files);
+    config.http = http;
+    config.manifests = manifests;
+    config.preferred_providers = preferred_providers;
+    config.resolve_dependencies = with_deps;
+    config.install_recommends = install_recommends;
+    config.install_suggests = install_suggests;
+    config.download_only = download_only;
+
+    CommandOptions { config, dry_run, print_uris, format }
+}
+
+/// 执行 `install` 或 `download`
+async fn run(options: CommandOptions) {
+    let CommandOptions { config, dry_run, print_uris, .. } = options;
+    if !print_uris {
+        let action = if config.download_only { "Downloading" } else { "Installing" };
+        println!("{} package with configuration:", action);
+        println!("Package name: {}", config.package_name);
+        println!("Mirrors (in priority order):");
+        for (index, mirror) in config.mirrors.iter().enumerate() {
+            println!("  {}: {}", index, mirror);
+        }
+        let architectures: Vec<String> = config
+            .architectures()
+            .map(|arch| format!("{} ({})", arch, arch.gnu_triplet()))
+            .collect();
+        println!("Architectures: {}", architectures.join(", "));
+        println!("Suite: {} ({})", config.suite, config.components.join(", "));
+        println!("{}: {}", if config.download_only { "Target directory" } else { "Root directory" }, config.root_dir.display());
+    }
+
+    let installer = Installer::builder(config)
+        .observer(Arc::new(TerminalProgress::new()))
+        .build().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+
+    if dry_run || print_uris {
+        match installer.plan().await {
+            Ok(plan) if print_uris => plan.print_uris().iter().for_each(|line| println!("{}", line)),
+            Ok(plan) => print_plan(&plan),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                process::exit(1);
+            }
+        }
+        return;
+    }
 
-            if mirror_url.is_empty() || architecture.is_empty() || root_dir.as_os_str().is_empty() || package_name.is_empty() {
-                eprintln!("Error: Missing required arguments");
-                print_usage();
+    println!("Downloading package...");
+    match installer.install().await {
+        Ok(report) => {
+            print_report(&report);
+            if !report.is_complete() {
                 process::exit(1);
             }
+            if !installer.config().download_only {
+                print_ndk_paths(installer.config());
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
 
-            println!("Installing package with configuration:");
-            println!("Package name: {}", package_name);
-            println!("Mirrors (in priority order):");
-            println!("  0: {}", mirror_url);
-            println!("Architecture: {}", architecture);
-            println!("Root directory: {}", root_dir.display());
+/// 执行 `why`：输出从顶层包到一个已安装包的依赖链
+fn run_why(config: &InstallConfig) {
+    let installed = StatusDb::load(&config.root_dir)
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        })
+        .installed_packages();
+    let native = config.architecture.as_str();
+    let (name, arch) = split_qualified_name(&config.package_name);
+    let Some(target) = installed.get(name).find(|p| arch.is_none_or(|arch| p.architecture == arch)) else {
+        eprintln!("{} is not installed", config.package_name);
+        process::exit(1);
+    };
+    let states = load_extended_states(config);
+    // 有安装标记时从手动安装的包出发，否则从没有被依赖的包出发
+    let roots = if states.is_empty() {
+        top_level(&installed, native)
+    } else {
+        manual_packages(&installed, &states, native)
+    };
+    match why(&installed, &roots, target, native) {
+        Some(chain) if chain.is_empty() && !states.is_empty() => {
+            println!("{} {} is manually installed", target.package, target.version);
+        }
+        Some(chain) if chain.is_empty() => {
+            println!("{} {} is not required by any other installed package", target.package, target.version);
+        }
+        Some(chain) => chain.iter().for_each(|link| println!("{}", link)),
+        None if !states.is_empty() => {
+            println!("{} is automatically installed and no longer required, autoremove will remove it", target.package);
+        }
+        None => println!("{} is only required by packages that depend on each other", target.package),
+    }
+}
+
+/// 读取安装标记，出错时退出
+fn load_extended_states(config: &InstallConfig) -> ExtendedStates {
+    ExtendedStates::load(&config.root_dir).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    })
+}
 
-            let config = InstallConfig::new(
-                package_name,
-                vec![mirror_url.clone()],
-                architecture,
-                root_dir,
-            ).unwrap_or_else(|e| {
+/// 执行 `autoremove`：删除不再被任何手动安装的包需要的自动安装的包
+fn run_autoremove(options: CommandOptions) {
+    let config = &options.config;
+    let installed = StatusDb::load(&config.root_dir)
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        })
+        .installed_packages();
+    let native = config.architecture.as_str();
+    let states = load_extended_states(config);
+    let orphans = autoremovable(&installed, &states, native);
+    if orphans.is_empty() {
+        println!("No packages to remove");
+        return;
+    }
+    println!("The following packages will be REMOVED:");
+    for package in &orphans {
+        println!("  {} {} ({})", package.package, package.version, package.architecture);
+    }
+    if options.dry_run {
+        return;
+    }
+    for package in &orphans {
+        let name = format!("{}:{}", package.package, package.architecture);
+        let result = StatusDb::update(&config.root_dir, |db| db.remove(&name)).and_then(|files| {
+            let arch = effective_architecture(&package.architecture, native);
+            ExtendedStates::update(&config.root_dir, |states| {
+                states.forget(&package.package, arch);
+                Ok(files)
+            })
+        });
+        match result {
+            Ok(files) => println!("Removed {} {} ({} files)", package.package, package.version, files),
+            Err(e) => {
                 eprintln!("Error: {}", e);
                 process::exit(1);
-            });
-
-            println!("Downloading package...");
-            for mirror in &config.mirrors {
-                match UrlBuilder::build_package_urls(&config, mirror).await {
-                    true => break,
-                    false => {
-                        eprintln!("Error: Download error: Failed to download package from mirror {}", mirror);
-                        continue;
-                    }
-                }
             }
         }
+    }
+}
+
+/// 执行 `mark auto|manual`：修改一个已安装包的安装标记
+fn run_mark(args: &[String]) {
+    let mark = match args.get(2).map(String::as_str) {
+        Some("auto") => Mark::Auto,
+        Some("manual") => Mark::Manual,
+        Some(other) => {
+            eprintln!("Error: Unknown mark: {} (expected auto or manual)", other);
+            process::exit(1);
+        }
+        None => {
+            eprintln!("Error: Missing mark (auto or manual)");
+            process::exit(1);
+        }
+    };
+    let config = parse_options(&args[3..], false, true).config;
+    let db = StatusDb::load(&config.root_dir).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    let Some(entry) = db.get(&config.package_name) else {
+        eprintln!("{} is not installed", config.package_name);
+        process::exit(1);
+    };
+    let name = entry.get("Package").unwrap_or_default();
+    let arch = effective_architecture(entry.get("Architecture").unwrap_or_default(), config.architecture.as_str());
+    let result = ExtendedStates::update(&config.root_dir, |states| {
+        let previous = states.mark(name, arch);
+        states.set(name, arch, mark);
+        Ok(previous)
+    });
+    let description = match mark {
+        Mark::Auto => "automatically installed",
+        Mark::Manual => "manually installed",
+    };
+    match result {
+        Ok(previous) if previous == mark => println!("{} was already set to {}", name, description),
+        Ok(_) => println!("{} set to {}", name, description),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// 执行 `why-not`：说明一个包为什么没有安装，或者为什么不能安装
+async fn run_why_not(config: InstallConfig) {
+    let name = config.package_name.clone();
+    let installed = StatusDb::load(&config.root_dir).map(|db| db.installed_version(&name).map(str::to_string));
+    if let Ok(Some(version)) = installed {
+        println!("{} {} is installed", name, version);
+        return;
+    }
+    let installer = Installer::builder(config).build().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    match installer.plan().await {
+        Ok(plan) => {
+            println!("{} is not installed, but it can be installed together with:", name);
+            for package in &plan.packages {
+                println!("  {} {} ({})", package.name, package.version, package.architecture);
+            }
+        }
+        Err(e) => {
+            println!("{} cannot be installed: {}", name, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// 创建不输出进度的安装器，以及 `depends`、`rdepends` 查询的范围：已安装的包，
+/// 配置了镜像源时再加上包索引
+async fn query_packages(config: InstallConfig) -> (Installer, PackageIndex, PackageIndex) {
+    let installer = Installer::builder(config).build().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    let installed = installer.installed_packages().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    let mut packages = if installer.config().mirrors.is_empty() {
+        PackageIndex::new()
+    } else {
+        installer.package_index().await.unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        })
+    };
+    packages.extend(installed.iter().cloned());
+    (installer, packages, installed)
+}
+
+/// 查找命令行请求的包，包索引中的候选版本优先于已安装的版本
+fn requested_package<'a>(config: &InstallConfig, packages: &'a PackageIndex) -> &'a PackageInfo {
+    let (name, arch) = split_qualified_name(&config.package_name);
+    let arch = arch.unwrap_or(config.architecture.as_str());
+    packages.find(name, arch).unwrap_or_else(|| {
+        eprintln!("Error: {}", Error::PackageNotFound {
+            name: name.to_string(),
+            architecture: arch.to_string(),
+            suggestion: None,
+        });
+        process::exit(1);
+    })
+}
+
+/// 执行 `depends`：以 `apt-cache depends` 的格式输出包的关系字段
+async fn run_depends(config: InstallConfig) {
+    let (installer, packages, _) = query_packages(config).await;
+    let package = requested_package(installer.config(), &packages);
+    println!("{} {} ({})", package.package, package.version, package.architecture);
+    for (field, group) in package.dependency_fields().chain(package.soft_dependency_fields()) {
+        for (index, dependency) in group.iter().enumerate() {
+            let marker = if index + 1 < group.len() { '|' } else { ' ' };
+            println!(" {}{}: {}", marker, field, dependency);
+        }
+    }
+    let fields = [
+        ("Conflicts", &package.conflicts),
+        ("Breaks", &package.breaks),
+        ("Replaces", &package.replaces),
+        ("Provides", &package.provides),
+    ];
+    for (field, relations) in fields {
+        for relation in relations {
+            println!("  {}: {}", field, relation);
+        }
+    }
+}
+
+/// 执行 `rdepends`：输出依赖一个包（或它提供的虚包）的所有包
+async fn run_rdepends(config: InstallConfig) {
+    let (installer, packages, installed) = query_packages(config).await;
+    let package = requested_package(installer.config(), &packages);
+    println!("{} {} ({})", package.package, package.version, package.architecture);
+    println!("Reverse Depends:");
+    for dependent in reverse_dependencies(&packages, package) {
+        let marker = if installed.contains(dependent) { " [installed]" } else { "" };
+        println!("  {} {} ({}){}", dependent.package, dependent.version, dependent.architecture, marker);
+    }
+}
+
+/// 执行 `graph`：解析请求的包并输出依赖图
+async fn run_graph(options: CommandOptions) {
+    let installer = Installer::builder(options.config).build().unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    });
+    match installer.dependency_graph().await {
+        Ok(graph) => match options.format {
+            GraphFormat::Dot => print!("{}", graph.to_dot()),
+            GraphFormat::Json => print!("{}", graph.to_json()),
+        },
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// 读取命令行参数指定的 `.deb` 文件，出错时退出
+fn open_deb(args: &[String]) -> DebArchive {
+    let path = match args.get(2) {
+        Some(path) => path,
+        None => {
+            eprintln!("Error: Missing .deb file");
+            process::exit(1);
+        }
+    };
+    DebArchive::open(Path::new(path)).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    })
+}
+
+/// 检查输出命令的结果，读取端提前关闭管道（例如 `| head`）时安静退出
+fn finish_output(result: mini_apt::error::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(mini_apt::error::Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        print_usage();
+        process::exit(1);
+    }
+
+    match args[1].as_str() {
+        "install" => run(parse_command_options(&args, false)).await,
+        "download" => run(parse_command_options(&args, true)).await,
+        "why" => run_why(&parse_command_options(&args, false).config),
+        "why-not" => run_why_not(parse_command_options(&args, false).config).await,
+        "depends" => run_depends(parse_command_options(&args, false).config).await,
+        "rdepends" => run_rdepends(parse_command_options(&args, false).config).await,
+        "graph" => run_graph(parse_command_options(&args, false)).await,
+        "autoremove" => run_autoremove(parse_options(&args[2..], false, false)),
+        "mark" => run_mark(&args),
+        "info" => finish_output(open_deb(&args).write_info(&mut io::stdout().lock())),
+        "contents" => finish_output(open_deb(&args).write_contents(&mut io::stdout().lock())),
         "echo" => {
             if args.len() < 3 {
                 eprintln!("Error: Missing text to echo");
@@ -0,0 +1,168 @@
+//! # 架构模块
+//!
+//! [`Architecture`] 表示一个 Debian 架构，例如 `arm64`、`amd64`。
+//!
+//! 解析时接受 Debian 架构名、常见的别名（`x86_64`、`aarch64`、`armv7l` 等）、GNU 三元组
+//! 和 multiarch 目录名，统一规范化为 Debian 架构名，因此可以直接用于镜像站的
+//! `binary-<arch>` 路径和 `.deb` 文件名。未知的名称会返回错误，并在可能时给出拼写建议。
+//!
+//! `all` 不是一个机器架构，只出现在包的 `Architecture` 字段中，不能作为目标架构。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::architecture::Architecture;
+//!
+//! let arch: Architecture = "aarch64".parse().unwrap();
+//! assert_eq!(arch, Architecture::Arm64);
+//! assert_eq!(arch.as_str(), "arm64");
+//! assert_eq!(arch.gnu_triplet(), "aarch64-linux-gnu");
+//!
+//! let i386: Architecture = "i686".parse().unwrap();
+//! assert_eq!(i386.gnu_triplet(), "i686-linux-gnu");
+//! assert_eq!(i386.multiarch_dir(), "i386-linux-gnu");
+//!
+//! let error = "amd46".parse::<Architecture>().unwrap_err();
+//! assert!(error.to_string().contains("did you mean amd64?"));
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::utils::text::closest_match;
+
+/// Debian 架构
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Architecture {
+    /// 64 位 x86
+    Amd64,
+    /// 64 位 ARM
+    Arm64,
+    /// 32 位 ARM，硬件浮点（ARMv7）
+    Armhf,
+    /// 32 位 ARM，软件浮点
+    Armel,
+    /// 32 位 x86
+    I386,
+    /// 64 位 PowerPC，小端
+    Ppc64el,
+    /// IBM Z
+    S390x,
+    /// 64 位 RISC-V
+    Riscv64,
+    /// 64 位 MIPS，小端
+    Mips64el,
+}
+
+impl Architecture {
+    /// 所有支持的架构
+    pub const ALL: [Architecture; 9] = [
+        Architecture::Amd64,
+        Architecture::Arm64,
+        Architecture::Armhf,
+        Architecture::Armel,
+        Architecture::I386,
+        Architecture::Ppc64el,
+        Architecture::S390x,
+        Architecture::Riscv64,
+        Architecture::Mips64el,
+    ];
+
+    /// Debian 架构名
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Architecture::Amd64 => "amd64",
+            Architecture::Arm64 => "arm64",
+            Architecture::Armhf => "armhf",
+            Architecture::Armel => "armel",
+            Architecture::I386 => "i386",
+            Architecture::Ppc64el => "ppc64el",
+            Architecture::S390x => "s390x",
+            Architecture::Riscv64 => "riscv64",
+            Architecture::Mips64el => "mips64el",
+        }
+    }
+
+    /// GNU 三元组，即交叉编译工具链的前缀，例如 `aarch64-linux-gnu`
+    pub fn gnu_triplet(&self) -> &'static str {
+        match self {
+            Architecture::Amd64 => "x86_64-linux-gnu",
+            Architecture::Arm64 => "aarch64-linux-gnu",
+            Architecture::Armhf => "arm-linux-gnueabihf",
+            Architecture::Armel => "arm-linux-gnueabi",
+            Architecture::I386 => "i686-linux-gnu",
+            Architecture::Ppc64el => "powerpc64le-linux-gnu",
+            Architecture::S390x => "s390x-linux-gnu",
+            Architecture::Riscv64 => "riscv64-linux-gnu",
+            Architecture::Mips64el => "mips64el-linux-gnuabi64",
+        }
+    }
+
+    /// multiarch 目录名，即 `/usr/lib/<dir>` 中的目录
+    ///
+    /// 除 `i386` 外与 GNU 三元组相同。
+    pub fn multiarch_dir(&self) -> &'static str {
+        match self {
+            Architecture::I386 => "i386-linux-gnu",
+            other => other.gnu_triplet(),
+        }
+    }
+
+    /// 解析时接受的别名（不包括 Debian 架构名、GNU 三元组和 multiarch 目录名）
+    pub fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            Architecture::Amd64 => &["x86_64", "x86-64", "x64"],
+            Architecture::Arm64 => &["aarch64", "armv8"],
+            Architecture::Armhf => &["arm", "armv7", "armv7l", "armv7hl"],
+            Architecture::Armel => &["armv5", "armv5tel"],
+            Architecture::I386 => &["i486", "i586", "i686", "x86"],
+            Architecture::Ppc64el => &["ppc64le", "powerpc64le"],
+            Architecture::S390x => &[],
+            Architecture::Riscv64 => &["riscv"],
+            Architecture::Mips64el => &[],
+        }
+    }
+
+    /// 名称是否指向这个架构
+    fn matches(&self, name: &str) -> bool {
+        name == self.as_str()
+            || name == self.gnu_triplet()
+            || name == self.multiarch_dir()
+            || self.aliases().contains(&name)
+    }
+}
+
+impl FromStr for Architecture {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim().to_ascii_lowercase();
+        if let Some(arch) = Architecture::ALL.into_iter().find(|arch| arch.matches(&name)) {
+            return Ok(arch);
+        }
+        if name == "all" {
+            return Err(Error::Config(
+                "all is not a machine architecture, choose the architecture of the target system".to_string(),
+            ));
+        }
+        let known = Architecture::ALL.iter().map(Architecture::as_str).collect::<Vec<_>>();
+        let spellings = Architecture::ALL.iter().flat_map(|arch| {
+            [arch.as_str()].into_iter().chain(arch.aliases().iter().copied())
+        });
+        let message = match closest_match(&name, spellings) {
+            Some(suggestion) => {
+                let arch = Architecture::ALL.into_iter().find(|arch| arch.matches(suggestion)).unwrap();
+                format!("unknown architecture {}, did you mean {}?", s, arch)
+            }
+            None => format!("unknown architecture {}, expected one of: {}", s, known.join(", ")),
+        };
+        Err(Error::Config(message))
+    }
+}
+
+impl fmt::Display for Architecture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,160 @@
+//! # 校验和模块
+//!
+//! 这个模块提供了下载文件使用的校验和类型和增量计算器。
+//!
+//! Debian 仓库的 `Packages` 文件同时提供 MD5 和 SHA256，Android NDK 等外部文件
+//! 通常只提供 SHA-1 或 SHA-256，因此下载器需要支持多种算法。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::checksum::Checksum;
+//!
+//! let checksum = Checksum::Sha256(
+//!     "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string(),
+//! );
+//! let mut hasher = checksum.hasher();
+//! hasher.update(b"hello");
+//! assert!(checksum.verify(hasher, "hello.txt").is_ok());
+//! ```
+
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+
+use sha1::Digest;
+
+use crate::error::{Error, Result};
+
+/// 预期的校验和
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    /// MD5（十六进制）
+    Md5(String),
+    /// SHA-1（十六进制）
+    Sha1(String),
+    /// SHA-256（十六进制）
+    Sha256(String),
+}
+
+impl Checksum {
+    /// 算法名称
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            Checksum::Md5(_) => "MD5",
+            Checksum::Sha1(_) => "SHA1",
+            Checksum::Sha256(_) => "SHA256",
+        }
+    }
+
+    /// 十六进制形式的校验和
+    pub fn hex(&self) -> &str {
+        match self {
+            Checksum::Md5(hex) | Checksum::Sha1(hex) | Checksum::Sha256(hex) => hex,
+        }
+    }
+
+    /// 创建对应算法的增量计算器
+    pub fn hasher(&self) -> Hasher {
+        match self {
+            Checksum::Md5(_) => Hasher::Md5(md5::Context::new()),
+            Checksum::Sha1(_) => Hasher::Sha1(sha1::Sha1::new()),
+            Checksum::Sha256(_) => Hasher::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    /// 计算一段数据的校验和并与预期值比较
+    pub fn verify_bytes(&self, data: &[u8], file: &str) -> Result<()> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        self.verify(hasher, file)
+    }
+
+    /// 计算文件的校验和并与预期值比较
+    pub fn verify_file(&self, path: &Path) -> Result<()> {
+        let mut hasher = self.hasher();
+        hasher.update_file(path)?;
+        self.verify(hasher, &path.display().to_string())
+    }
+
+    /// 计算一段数据的 SHA-256
+    pub fn sha256_of_bytes(data: &[u8]) -> Self {
+        Checksum::Sha256(hex::encode(sha2::Sha256::digest(data)))
+    }
+
+    /// 计算文件的 SHA-256
+    pub fn sha256_of_file(path: &Path) -> Result<Self> {
+        let mut hasher = Hasher::Sha256(sha2::Sha256::new());
+        hasher.update_file(path)?;
+        Ok(Checksum::Sha256(hasher.finish()))
+    }
+
+    /// 比较计算结果与预期值
+    ///
+    /// # 错误
+    ///
+    /// 不匹配时返回 [`Error::Checksum`]
+    pub fn verify(&self, hasher: Hasher, file: &str) -> Result<()> {
+        let actual = hasher.finish();
+        if actual.eq_ignore_ascii_case(self.hex()) {
+            Ok(())
+        } else {
+            Err(Error::Checksum {
+                algorithm: self.algorithm(),
+                file: file.to_string(),
+                expected: self.hex().to_string(),
+                actual,
+            })
+        }
+    }
+}
+
+impl fmt::Display for Checksum {
+    /// 以 APT `--print-uris` 的格式输出，例如 `SHA256:abcd...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm(), self.hex())
+    }
+}
+
+/// 增量校验和计算器
+pub enum Hasher {
+    /// MD5
+    Md5(md5::Context),
+    /// SHA-1
+    Sha1(sha1::Sha1),
+    /// SHA-256
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(context) => context.consume(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// 追加整个文件的内容
+    pub fn update_file(&mut self, path: &Path) -> Result<()> {
+        let mut file = std::fs::File::open(path).map_err(|e| Error::io_at(path, e))?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buffer).map_err(|e| Error::io_at(path, e))?;
+            if n == 0 {
+                return Ok(());
+            }
+            self.update(&buffer[..n]);
+        }
+    }
+
+    /// 结束计算，返回十六进制形式的校验和
+    pub fn finish(self) -> String {
+        match self {
+            Hasher::Md5(context) => format!("{:x}", context.compute()),
+            Hasher::Sha1(hasher) => hex::encode(hasher.finalize()),
+            Hasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
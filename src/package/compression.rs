@@ -0,0 +1,114 @@
+//! # 压缩格式模块
+//!
+//! 这个模块根据文件名后缀识别压缩格式，并为读取器套上对应的解压器。
+//! `.deb` 中的 `control.tar.*`、`data.tar.*` 成员以及仓库索引文件都使用这里的解压器。
+//!
+//! gzip 始终可用；xz、zstd 和 bzip2 分别由同名的 cargo feature 控制，默认全部启用。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::compression::Compression;
+//!
+//! assert_eq!(Compression::from_file_name("data.tar.gz"), Compression::Gzip);
+//! assert_eq!(Compression::from_file_name("data.tar"), Compression::None);
+//! ```
+
+use std::fmt;
+use std::io::Read;
+
+use crate::error::{Error, Result};
+
+/// 压缩格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// 未压缩
+    None,
+    /// gzip（`.gz`）
+    Gzip,
+    /// xz（`.xz`）
+    Xz,
+    /// zstd（`.zst`）
+    Zstd,
+    /// bzip2（`.bz2`）
+    Bzip2,
+}
+
+impl Compression {
+    /// 根据文件名后缀识别压缩格式，无法识别的后缀视为未压缩
+    pub fn from_file_name(name: &str) -> Self {
+        match name.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("gz") => Compression::Gzip,
+            Some("xz") => Compression::Xz,
+            Some("zst") => Compression::Zstd,
+            Some("bz2") => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+
+    /// 文件名后缀（不含 `.`），未压缩时为空字符串
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gz",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zst",
+            Compression::Bzip2 => "bz2",
+        }
+    }
+
+    /// 当前构建是否支持该压缩格式
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Compression::None | Compression::Gzip => true,
+            Compression::Xz => cfg!(feature = "xz"),
+            Compression::Zstd => cfg!(feature = "zstd"),
+            Compression::Bzip2 => cfg!(feature = "bzip2"),
+        }
+    }
+
+    /// 为读取器套上解压器
+    ///
+    /// 多个压缩流首尾相接时（例如 `pxz`、`pbzip2` 的输出）全部解压。
+    ///
+    /// # 错误
+    ///
+    /// 构建时没有启用该压缩格式时返回 [`Error::Parse`]
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> Result<Box<dyn Read + 'a>> {
+        match self {
+            Compression::None => Ok(Box::new(reader)),
+            Compression::Gzip => Ok(Box::new(flate2::read::MultiGzDecoder::new(reader))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Box::new(xz2::read::XzDecoder::new_multi_decoder(reader))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => Ok(Box::new(bzip2::read::MultiBzDecoder::new(reader))),
+            #[allow(unreachable_patterns)]
+            other => Err(Error::Parse(format!(
+                "unsupported compression: {} (built without the \"{}\" feature)",
+                other,
+                other.feature()
+            ))),
+        }
+    }
+
+    /// 提供该压缩格式的 cargo feature 名
+    fn feature(&self) -> &'static str {
+        match self {
+            Compression::None | Compression::Gzip => "",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => f.write_str("none"),
+            other => f.write_str(other.extension()),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! # 控制文件解析模块
+//!
+//! 这个模块解析 Debian 控制文件格式（deb822），即 `Packages`、`Release`、
+//! `status` 等文件使用的 `字段: 值` 段落格式。
+//!
+//! - 段落之间以空行分隔
+//! - 以空格或制表符开头的行是上一个字段的续行
+//! - 以 `#` 开头的行是注释
+//! - 字段名不区分大小写
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::control::parse_stanzas;
+//!
+//! let stanzas = parse_stanzas("Package: hello\nDescription: greeting\n more text\n\nPackage: world\n");
+//! assert_eq!(stanzas.len(), 2);
+//! assert_eq!(stanzas[0].get("package"), Some("hello"));
+//! assert_eq!(stanzas[0].get("Description"), Some("greeting\nmore text"));
+//! ```
+
+/// 控制文件中的一个段落
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stanza {
+    fields: Vec<(String, String)>,
+}
+
+impl Stanza {
+    /// 获取字段值（字段名不区分大小写）
+    ///
+    /// 多行字段的各行以 `\n` 连接，续行开头的空白会被去掉。
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 按原始顺序遍历所有字段
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// 设置字段值，已存在的字段会被替换
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.fields.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((name.to_string(), value)),
+        }
+    }
+
+    /// 段落是否没有任何字段
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl std::fmt::Display for Stanza {
+    /// 以控制文件格式输出段落，多行字段的续行以一个空格开头
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (key, value) in &self.fields {
+            let mut lines = value.split('\n');
+            writeln!(f, "{}: {}", key, lines.next().unwrap_or(""))?;
+            for line in lines {
+                writeln!(f, " {}", if line.is_empty() { "." } else { line })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析控制文件内容
+pub fn parse_stanzas(content: &str) -> Vec<Stanza> {
+    let mut stanzas = Vec::new();
+    let mut current = Stanza::default();
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                stanzas.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = current.fields.last_mut() {
+                let continuation = line.trim();
+                // 单独的 "." 表示空行
+                let continuation = if continuation == "." { "" } else { continuation };
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(continuation);
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            current.fields.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if !current.is_empty() {
+        stanzas.push(current);
+    }
+    stanzas
+}
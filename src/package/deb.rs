@@ -0,0 +1,443 @@
+//! # .deb 归档模块
+//!
+//! 这个模块读取 Debian 二进制包。`.deb` 文件是一个 `ar` 归档，依次包含：
+//!
+//! - `debian-binary`: 格式版本，目前为 `2.0`
+//! - `control.tar[.压缩后缀]`: 控制文件和维护脚本
+//! - `data.tar[.压缩后缀]`: 安装到根目录的文件
+//!
+//! 压缩格式由成员名的后缀决定，见 [`Compression`]。安装和查看（[`DebArchive::control_members`]、
+//! [`DebArchive::contents`]）使用同一个读取器。
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::package::deb::DebArchive;
+//! use std::path::Path;
+//!
+//! let deb = DebArchive::open(Path::new("hello_2.10-2_arm64.deb")).unwrap();
+//! let control = deb.control().unwrap();
+//! println!("{} {}", control.get("Package").unwrap_or(""), control.get("Version").unwrap_or(""));
+//! let files = deb.extract_data(Path::new("sysroot")).unwrap();
+//! println!("{} entries", files.len());
+//! ```
+
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::checksum::Checksum;
+use super::compression::Compression;
+use super::control::{parse_stanzas, Stanza};
+use super::extract::{extract_tar_files, sanitize_path};
+use super::package_info::read_control_fields;
+use super::status::StatusDb;
+use super::PackageInfo;
+use crate::error::{Error, Result};
+
+/// 维护脚本的文件名
+pub const MAINTAINER_SCRIPTS: &[&str] = &["preinst", "postinst", "prerm", "postrm", "config"];
+
+/// 一个 `.deb` 文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebArchive {
+    path: PathBuf,
+    version: String,
+}
+
+/// `ar` 归档的一个成员
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// 成员名，例如 `data.tar.xz`
+    pub name: String,
+    /// 大小（字节）
+    pub size: u64,
+}
+
+/// `control.tar` 中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlMember {
+    /// 文件名，例如 `postinst`
+    pub name: String,
+    /// 大小（字节）
+    pub size: u64,
+    /// 行数
+    pub lines: usize,
+    /// 权限位
+    pub mode: u32,
+}
+
+impl ControlMember {
+    /// 是否是维护脚本
+    pub fn is_maintainer_script(&self) -> bool {
+        MAINTAINER_SCRIPTS.contains(&self.name.as_str())
+    }
+}
+
+/// tar 条目的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// 普通文件
+    File,
+    /// 目录
+    Directory,
+    /// 符号链接
+    Symlink,
+    /// 硬链接
+    HardLink,
+    /// 字符设备
+    CharDevice,
+    /// 块设备
+    BlockDevice,
+    /// 命名管道
+    Fifo,
+    /// 其他类型
+    Other,
+}
+
+impl EntryKind {
+    fn from_tar(kind: tar::EntryType) -> Self {
+        match kind {
+            tar::EntryType::Regular | tar::EntryType::Continuous => EntryKind::File,
+            tar::EntryType::Directory => EntryKind::Directory,
+            tar::EntryType::Symlink => EntryKind::Symlink,
+            tar::EntryType::Link => EntryKind::HardLink,
+            tar::EntryType::Char => EntryKind::CharDevice,
+            tar::EntryType::Block => EntryKind::BlockDevice,
+            tar::EntryType::Fifo => EntryKind::Fifo,
+            _ => EntryKind::Other,
+        }
+    }
+
+    /// `ls -l` 风格的类型字符
+    fn symbol(&self) -> char {
+        match self {
+            EntryKind::File | EntryKind::HardLink | EntryKind::Other => '-',
+            EntryKind::Directory => 'd',
+            EntryKind::Symlink => 'l',
+            EntryKind::CharDevice => 'c',
+            EntryKind::BlockDevice => 'b',
+            EntryKind::Fifo => 'p',
+        }
+    }
+}
+
+/// `data.tar` 中的一个条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// 条目路径，保留归档中的写法，例如 `./usr/bin/hello`
+    pub path: String,
+    /// 条目类型
+    pub kind: EntryKind,
+    /// 权限位
+    pub mode: u32,
+    /// 所有者用户名（归档中没有时为 uid）
+    pub owner: String,
+    /// 所属组名（归档中没有时为 gid）
+    pub group: String,
+    /// 大小（字节）
+    pub size: u64,
+    /// 修改时间（Unix 时间戳）
+    pub mtime: u64,
+    /// 符号链接或硬链接的目标
+    pub link_target: Option<String>,
+}
+
+impl ArchiveEntry {
+    /// `ls -l` 风格的权限字符串，例如 `-rwxr-xr-x`
+    pub fn mode_string(&self) -> String {
+        let mut result = String::with_capacity(10);
+        result.push(self.kind.symbol());
+        for (shift, special, lower, upper) in [(6, 0o4000, 's', 'S'), (3, 0o2000, 's', 'S'), (0, 0o1000, 't', 'T')] {
+            let bits = (self.mode >> shift) & 0o7;
+            result.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            result.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            result.push(match (bits & 0o1 != 0, self.mode & special != 0) {
+                (true, true) => lower,
+                (false, true) => upper,
+                (true, false) => 'x',
+                (false, false) => '-',
+            });
+        }
+        result
+    }
+}
+
+impl std::fmt::Display for ArchiveEntry {
+    /// 以 `dpkg-deb --contents` 的格式输出
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = chrono::DateTime::from_timestamp(self.mtime as i64, 0)
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        write!(f, "{} {}/{} {:>9} {} {}", self.mode_string(), self.owner, self.group, self.size, time, self.path)?;
+        match (&self.kind, &self.link_target) {
+            (EntryKind::Symlink, Some(target)) => write!(f, " -> {}", target),
+            (EntryKind::HardLink, Some(target)) => write!(f, " link to {}", target),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DebArchive {
+    /// 打开 `.deb` 文件并检查格式版本
+    ///
+    /// # 错误
+    ///
+    /// 文件不是 `ar` 归档，或 `debian-binary` 不是 2.x 版本时返回 [`Error::Parse`]
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut deb = Self { path: path.to_path_buf(), version: String::new() };
+        let version = deb.with_member("debian-binary", |_, mut reader| {
+            let mut version = String::new();
+            reader.read_to_string(&mut version).map_err(|e| deb.invalid(e))?;
+            Ok(version)
+        })?;
+        if !version.trim().starts_with("2.") {
+            return Err(Error::Parse(format!(
+                "{}: unsupported .deb format version {}",
+                path.display(),
+                version.trim()
+            )));
+        }
+        deb.version = version.trim().to_string();
+        Ok(deb)
+    }
+
+    /// 文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 格式版本，即 `debian-binary` 的内容，例如 `2.0`
+    pub fn format_version(&self) -> &str {
+        &self.version
+    }
+
+    /// 列出 `ar` 归档的成员
+    pub fn members(&self) -> Result<Vec<Member>> {
+        let file = fs::File::open(&self.path).map_err(|e| Error::io_at(&self.path, e))?;
+        let mut archive = ar::Archive::new(BufReader::new(file));
+        let mut members = Vec::new();
+        while let Some(entry) = archive.next_entry() {
+            let entry = entry.map_err(|e| self.invalid(e))?;
+            members.push(Member {
+                name: member_name(&entry),
+                size: entry.header().size(),
+            });
+        }
+        Ok(members)
+    }
+
+    /// 列出 `control.tar` 中的文件（控制文件和维护脚本等）
+    pub fn control_members(&self) -> Result<Vec<ControlMember>> {
+        self.with_member("control.tar", |_, reader| {
+            let mut archive = tar::Archive::new(reader);
+            let mut members = Vec::new();
+            for entry in archive.entries().map_err(|e| self.invalid(e))? {
+                let mut entry = entry.map_err(|e| self.invalid(e))?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = entry.path().map_err(|e| self.invalid(e))?.into_owned();
+                let mode = entry.header().mode().unwrap_or(0);
+                let mut content = Vec::new();
+                entry.read_to_end(&mut content).map_err(|e| self.invalid(e))?;
+                members.push(ControlMember {
+                    name: path.strip_prefix("./").unwrap_or(&path).display().to_string(),
+                    size: content.len() as u64,
+                    lines: content.iter().filter(|b| **b == b'\n').count(),
+                    mode,
+                });
+            }
+            Ok(members)
+        })
+    }
+
+    /// 列出 `data.tar` 中的条目，不解包
+    pub fn contents(&self) -> Result<Vec<ArchiveEntry>> {
+        self.with_member("data.tar", |_, reader| {
+            let mut archive = tar::Archive::new(reader);
+            let mut entries = Vec::new();
+            for entry in archive.entries().map_err(|e| self.invalid(e))? {
+                let entry = entry.map_err(|e| self.invalid(e))?;
+                let header = entry.header();
+                let name = |value: Option<&[u8]>, id: u64| match value {
+                    Some(name) if !name.is_empty() => String::from_utf8_lossy(name).into_owned(),
+                    _ => id.to_string(),
+                };
+                let uid = header.uid().unwrap_or(0);
+                let gid = header.gid().unwrap_or(0);
+                entries.push(ArchiveEntry {
+                    path: String::from_utf8_lossy(&entry.path_bytes()).into_owned(),
+                    kind: EntryKind::from_tar(header.entry_type()),
+                    mode: header.mode().unwrap_or(0),
+                    owner: name(header.username_bytes(), uid),
+                    group: name(header.groupname_bytes(), gid),
+                    size: header.size().unwrap_or(0),
+                    mtime: header.mtime().unwrap_or(0),
+                    link_target: entry
+                        .link_name_bytes()
+                        .map(|target| String::from_utf8_lossy(&target).into_owned()),
+                });
+            }
+            Ok(entries)
+        })
+    }
+
+    /// 以 `dpkg-deb --info` 的格式输出包的信息
+    ///
+    /// 依次输出格式版本、文件和控制归档的大小、控制归档中的每个文件、控制字段和维护脚本列表。
+    ///
+    /// # 错误
+    ///
+    /// 读取归档失败，或者写入 `out` 失败时返回错误；写入错误为 [`Error::Io`]，调用者可以据此
+    /// 识别 [`io::ErrorKind::BrokenPipe`]
+    pub fn write_info(&self, out: &mut impl Write) -> Result<()> {
+        let members = self.members()?;
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        let control_size = members
+            .iter()
+            .find(|member| member.name.starts_with("control.tar"))
+            .map_or(0, |member| member.size);
+        writeln!(out, " new Debian package, version {}.", self.format_version())?;
+        writeln!(out, " size {} bytes: control archive={} bytes.", size, control_size)?;
+        let files = self.control_members()?;
+        for file in &files {
+            let executable = if file.mode & 0o111 != 0 { '*' } else { ' ' };
+            writeln!(out, " {:>7} bytes, {:>5} lines   {}  {}", file.size, file.lines, executable, file.name)?;
+        }
+        for line in self.control()?.to_string().lines() {
+            writeln!(out, " {}", line)?;
+        }
+        let scripts: Vec<&str> = files
+            .iter()
+            .filter(|file| file.is_maintainer_script())
+            .map(|file| file.name.as_str())
+            .collect();
+        if scripts.is_empty() {
+            writeln!(out, " Maintainer scripts: (none)")?;
+        } else {
+            writeln!(out, " Maintainer scripts: {}", scripts.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// 以 `dpkg-deb --contents` 的格式列出 `data.tar` 中的条目，每行一个
+    ///
+    /// # 错误
+    ///
+    /// 与 [`DebArchive::write_info`] 相同
+    pub fn write_contents(&self, out: &mut impl Write) -> Result<()> {
+        for entry in self.contents()? {
+            writeln!(out, "{}", entry)?;
+        }
+        Ok(())
+    }
+
+    /// 读取 `control` 文件
+    pub fn control(&self) -> Result<Stanza> {
+        let content = self.with_member("control.tar", |_, reader| {
+            let mut archive = tar::Archive::new(reader);
+            for entry in archive.entries().map_err(|e| self.invalid(e))? {
+                let mut entry = entry.map_err(|e| self.invalid(e))?;
+                let path = entry.path().map_err(|e| self.invalid(e))?;
+                if path.strip_prefix("./").unwrap_or(&path) == Path::new("control") {
+                    let mut content = String::new();
+                    entry.read_to_string(&mut content).map_err(|e| self.invalid(e))?;
+                    return Ok(content);
+                }
+            }
+            Err(Error::Parse(format!("{}: control.tar has no control file", self.path.display())))
+        })?;
+        parse_stanzas(&content)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Parse(format!("{}: empty control file", self.path.display())))
+    }
+
+    /// 根据控制文件生成包信息
+    ///
+    /// `filename` 为本地路径，`size` 和 `sha256` 根据文件内容计算。
+    pub fn package_info(&self) -> Result<PackageInfo> {
+        let control = self.control()?;
+        let field = |key: &str| {
+            control
+                .get(key)
+                .map(str::to_string)
+                .ok_or_else(|| Error::Parse(format!("{}: control file has no {}", self.path.display(), key)))
+        };
+        let size = fs::metadata(&self.path).map_err(|e| Error::io_at(&self.path, e))?.len();
+        let mut package = PackageInfo::new(
+            field("Package")?,
+            field("Version")?,
+            field("Architecture")?,
+            self.path.display().to_string(),
+            size,
+            String::new(),
+            Checksum::sha256_of_file(&self.path)?.hex().to_string(),
+        );
+        read_control_fields(&mut package, &control)?;
+        Ok(package)
+    }
+
+    /// 把 `data.tar` 解包到目标目录
+    ///
+    /// # 返回值
+    ///
+    /// 解出的条目相对于 `dest` 的路径，包括目录
+    pub fn extract_data(&self, dest: &Path) -> Result<Vec<PathBuf>> {
+        self.with_member("data.tar", |_, reader| {
+            extract_tar_files(reader, dest, 0).map_err(|e| match e {
+                Error::Parse(message) => Error::Parse(format!("{}: {}", self.path.display(), message)),
+                other => other,
+            })
+        })
+    }
+
+    /// 把包安装到根目录：解包 `data.tar`，并记录到状态数据库
+    ///
+    /// # 返回值
+    ///
+    /// 成功返回解出的条目数
+    ///
+    /// # 错误
+    ///
+    /// 包中的文件属于另一个已安装、且没有被这个包 `Replaces` 的包时返回
+    /// [`Error::Conflict`]，此时不解出任何文件
+    pub fn install(&self, root_dir: &Path) -> Result<u64> {
+        let control = self.control()?;
+        let mut owned = Vec::new();
+        for entry in self.contents()?.iter().filter(|entry| entry.kind != EntryKind::Directory) {
+            owned.extend(sanitize_path(Path::new(&entry.path), 0)?);
+        }
+        StatusDb::load(root_dir)?.check_overwrite(&control, &owned)?;
+        let files = self.extract_data(root_dir)?;
+        StatusDb::update(root_dir, |db| db.record(&control, &files))?;
+        Ok(files.len() as u64)
+    }
+
+    /// 找到名为 `name`（或 `name.压缩后缀`）的成员，并以解压后的读取器调用 `f`
+    fn with_member<T>(&self, name: &str, f: impl FnOnce(&str, Box<dyn Read + '_>) -> Result<T>) -> Result<T> {
+        let file = fs::File::open(&self.path).map_err(|e| Error::io_at(&self.path, e))?;
+        let mut archive = ar::Archive::new(BufReader::new(file));
+        while let Some(entry) = archive.next_entry() {
+            let entry = entry.map_err(|e| self.invalid(e))?;
+            let member = member_name(&entry);
+            let matches = member == name
+                || member.strip_prefix(name).is_some_and(|rest| rest.starts_with('.'));
+            if matches {
+                let reader = Compression::from_file_name(&member).decoder(entry)?;
+                return f(&member, reader);
+            }
+        }
+        Err(Error::Parse(format!("{}: missing {} member", self.path.display(), name)))
+    }
+
+    fn invalid(&self, e: io::Error) -> Error {
+        Error::Parse(format!("{}: invalid .deb archive: {}", self.path.display(), e))
+    }
+}
+
+/// `ar` 成员名，去掉 GNU ar 添加的结尾 `/`
+fn member_name<R: Read>(entry: &ar::Entry<'_, R>) -> String {
+    String::from_utf8_lossy(entry.header().identifier()).trim_end_matches('/').to_string()
+}
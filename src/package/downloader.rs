@@ -5,23 +5,28 @@
 //! ## 主要功能
 //! 
 //! - 异步下载单个包
-//! - 并行下载多个包
-//! - MD5 校验
+//! - 并行下载多个包，限制全局和每个主机的并发连接数
+//! - 所有下载共享同一个带连接池的 HTTP 客户端
+//! - MD5、SHA-1、SHA-256 校验，边下载边计算
 //! - 自动创建目录
 //! 
 //! ## 示例
 //! 
 //! ```rust,no_run
-//! use mini_apt::package::downloader::{download_package, download_packages};
+//! use mini_apt::package::downloader::{
+//!     download_package, download_packages, DownloadLimits, DownloadManager, DownloadRequest,
+//! };
+//! use mini_apt::progress::SilentObserver;
 //! use std::path::PathBuf;
 //! 
 //! #[tokio::main]
-//! async fn main() -> Result<(), String> {
+//! async fn main() -> Result<(), mini_apt::error::Error> {
 //!     // 下载单个包
 //!     download_package(
 //!         "https://example.com/package.deb".to_string(),
 //!         PathBuf::from("downloads"),
 //!         "abcdef1234567890".to_string(),
+//!         &SilentObserver,
 //!     ).await?;
 //! 
 //!     // 并行下载多个包
@@ -33,27 +38,377 @@
 //!          PathBuf::from("downloads"),
 //!          "0123456789abcdef".to_string()),
 //!     ];
-//!     download_packages(downloads).await?;
+//!     download_packages(downloads, &SilentObserver).await?;
+//! 
+//!     // 使用下载管理器控制并发数
+//!     let manager = DownloadManager::new(DownloadLimits { max_concurrent: 4, max_per_host: 2 })?;
+//!     let results = manager.download_all(vec![
+//!         DownloadRequest::new("https://example.com/package3.deb", "downloads", "abcdef1234567890"),
+//!     ], &SilentObserver).await;
+//!     for result in results {
+//!         result?;
+//!     }
 //!     Ok(())
 //! }
 //! ```
 
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use reqwest::Client;
 use std::fs;
-use tokio::io::AsyncWriteExt;
+use std::io::Write;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::checksum::Checksum;
+use crate::error::{Error, Result};
+use crate::progress::{ProgressEvent, ProgressObserver};
+use crate::utils::auth::AuthConf;
+use crate::utils::http::{build_client, HttpOptions, StallDetection};
+
+/// 下载时使用的 User-Agent
+pub const USER_AGENT: &str = "Debian APT-HTTP/1.3 (2.0.9)";
+
+/// 已下载文件的信息
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    /// 下载的 URL
+    pub url: String,
+    /// 保存的本地路径
+    pub path: PathBuf,
+    /// 传输的字节数
+    pub bytes: u64,
+}
+
+/// 一个下载请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadRequest {
+    /// 下载 URL
+    pub url: String,
+    /// 下载目标目录
+    pub dest_dir: PathBuf,
+    /// 预期的校验和
+    pub checksum: Checksum,
+    /// 保存的文件名，未指定时使用 URL 的最后一段
+    pub file_name: Option<String>,
+}
+
+impl DownloadRequest {
+    /// 创建使用 MD5 校验的下载请求
+    pub fn new(url: impl Into<String>, dest_dir: impl Into<PathBuf>, expected_md5: impl Into<String>) -> Self {
+        Self::with_checksum(url, dest_dir, Checksum::Md5(expected_md5.into()))
+    }
+
+    /// 创建使用指定校验和的下载请求
+    pub fn with_checksum(url: impl Into<String>, dest_dir: impl Into<PathBuf>, checksum: Checksum) -> Self {
+        Self {
+            url: url.into(),
+            dest_dir: dest_dir.into(),
+            checksum,
+            file_name: None,
+        }
+    }
+
+    /// 指定保存的文件名
+    ///
+    /// 同一个文件的多个候选 URL 文件名不同时，用来保证保存位置一致。
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+}
+
+/// 下载并发限制
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadLimits {
+    /// 全局最大并发下载数
+    pub max_concurrent: usize,
+    /// 每个主机的最大并发下载数
+    pub max_per_host: usize,
+}
+
+impl Default for DownloadLimits {
+    /// 默认值与 APT 的 `Acquire::Queue-Mode "host"` 行为相近：
+    /// 全局最多 16 个连接，每个主机最多 4 个
+    fn default() -> Self {
+        Self {
+            max_concurrent: 16,
+            max_per_host: 4,
+        }
+    }
+}
+
+/// 下载管理器
+/// 
+/// 所有下载共享同一个 [`Client`]，从而复用连接池中的连接。并发数由两级信号量控制：
+/// 请求先获取所属主机的许可，再获取全局许可。由于 Tokio 的信号量按先来先服务的顺序
+/// 分配许可，等待同一个主机的请求不会占用全局名额，其他主机的请求可以继续进行，
+/// 各个请求按提交顺序公平地得到调度。
+#[derive(Debug)]
+pub struct DownloadManager {
+    client: Client,
+    limits: DownloadLimits,
+    read_timeout: Option<Duration>,
+    stall: Option<StallDetection>,
+    auth: AuthConf,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl DownloadManager {
+    /// 使用默认 HTTP 客户端创建下载管理器
+    pub fn new(limits: DownloadLimits) -> Result<Self> {
+        Self::with_options(limits, &HttpOptions::default())
+    }
+
+    /// 根据 HTTP 选项创建下载管理器
+    /// 
+    /// 代理、证书和连接超时应用于 HTTP 客户端，读取超时、停滞检测和认证由下载管理器执行。
+    pub fn with_options(limits: DownloadLimits, options: &HttpOptions) -> Result<Self> {
+        let client = build_client(options)?;
+        let mut manager = Self::with_client(client, limits);
+        manager.read_timeout = options.read_timeout;
+        manager.stall = options.stall;
+        manager.auth = options.auth.clone();
+        Ok(manager)
+    }
+
+    /// 使用已有的 HTTP 客户端创建下载管理器
+    /// 
+    /// 限制值为 0 时按 1 处理。
+    pub fn with_client(client: Client, limits: DownloadLimits) -> Self {
+        let limits = DownloadLimits {
+            max_concurrent: limits.max_concurrent.max(1),
+            max_per_host: limits.max_per_host.max(1),
+        };
+        Self {
+            client,
+            limits,
+            read_timeout: None,
+            stall: None,
+            auth: AuthConf::default(),
+            global: Arc::new(Semaphore::new(limits.max_concurrent)),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 共享的 HTTP 客户端
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// 当前的并发限制
+    pub fn limits(&self) -> DownloadLimits {
+        self.limits
+    }
+
+    fn host_semaphore(&self, url: &str) -> Arc<Semaphore> {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.limits.max_per_host)))
+            .clone()
+    }
+
+    /// 在并发限制下获取 URL 的完整内容
+    /// 
+    /// 下载过程中会向 `observer` 报告进度事件。
+    pub async fn fetch(&self, url: &str, observer: &dyn ProgressObserver) -> Result<Vec<u8>> {
+        let _permits = self.acquire(url).await;
+        let mut content = Vec::new();
+        self.stream(url, observer, |chunk| {
+            content.extend_from_slice(chunk);
+            Ok(())
+        }).await?;
+        Ok(content)
+    }
+
+    /// 等待 URL 所在主机和全局的并发许可，许可在返回值释放时归还
+    async fn acquire(&self, url: &str) -> (OwnedSemaphorePermit, OwnedSemaphorePermit) {
+        // 信号量不会被关闭，获取许可不会失败
+        let host = self.host_semaphore(url).acquire_owned().await.expect("host semaphore closed");
+        let global = self.global.clone().acquire_owned().await.expect("global semaphore closed");
+        (host, global)
+    }
+
+    /// 获取 URL 的内容，每收到一段数据就交给 `on_chunk` 处理
+    /// 
+    /// 调用者需要先通过 [`acquire`](Self::acquire) 取得并发许可。返回传输的总字节数。
+    async fn stream(
+        &self,
+        url: &str,
+        observer: &dyn ProgressObserver,
+        mut on_chunk: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<u64> {
+        let mut request = self.client.get(url);
+        if let Some(credentials) = self.auth.credentials_for(url) {
+            request = request.basic_auth(&credentials.login, Some(&credentials.password));
+        }
+        let mut response = self.with_read_timeout(url, request.send())
+            .await?
+            .map_err(|e| Error::http(url, e))?;
+
+        if !response.status().is_success() {
+            return Err(Error::Status { url: url.to_string(), status: response.status().as_u16() });
+        }
+
+        let total = response.content_length();
+        observer.on_event(&ProgressEvent::DownloadStarted { url: url.to_string(), total });
+        let mut received = 0u64;
+        let mut window_start = (Instant::now(), 0u64);
+        loop {
+            let next = self.with_read_timeout(url, response.chunk());
+            // 对端完全停止发送时不会再收到数据，等待下一段数据本身也受统计窗口的限制
+            let next = match self.stall {
+                Some(stall) => tokio::time::timeout(stall.window, next).await.map_err(|_| {
+                    stalled(url, received - window_start.1, window_start.0.elapsed(), stall)
+                })?,
+                None => next.await,
+            };
+            let Some(chunk) = next?.map_err(|e| Error::http(url, e))? else {
+                break;
+            };
+            on_chunk(&chunk)?;
+            received += chunk.len() as u64;
+            if let Some(stall) = self.stall {
+                let elapsed = window_start.0.elapsed();
+                if elapsed >= stall.window {
+                    let bytes = received - window_start.1;
+                    if bytes < stall.min_bytes_per_sec.saturating_mul(elapsed.as_secs().max(1)) {
+                        return Err(stalled(url, bytes, elapsed, stall));
+                    }
+                    window_start = (Instant::now(), received);
+                }
+            }
+            observer.on_event(&ProgressEvent::BytesReceived {
+                url: url.to_string(),
+                chunk: chunk.len() as u64,
+                received,
+                total,
+            });
+        }
+        observer.on_event(&ProgressEvent::DownloadFinished { url: url.to_string(), bytes: received });
+        Ok(received)
+    }
+
+    /// 在读取超时的限制下等待一个操作完成
+    async fn with_read_timeout<T>(&self, url: &str, future: impl std::future::Future<Output = T>) -> Result<T> {
+        match self.read_timeout {
+            Some(limit) => tokio::time::timeout(limit, future).await.map_err(|_| Error::Timeout {
+                url: url.to_string(),
+                reason: format!("no data received for {:?}", limit),
+            }),
+            None => Ok(future.await),
+        }
+    }
+
+    /// 下载单个文件并校验
+    /// 
+    /// 失败时除了返回错误，还会向 `observer` 报告 [`ProgressEvent::Error`]。
+    pub async fn download(&self, request: &DownloadRequest, observer: &dyn ProgressObserver) -> Result<DownloadedFile> {
+        let result = self.download_inner(request, observer).await;
+        if let Err(e) = &result {
+            observer.on_event(&ProgressEvent::Error { subject: request.url.clone(), message: e.to_string() });
+        }
+        result
+    }
+
+    async fn download_inner(&self, request: &DownloadRequest, observer: &dyn ProgressObserver) -> Result<DownloadedFile> {
+        let url = request.url.as_str();
+        let dest_dir = absolute_dir(&request.dest_dir)?;
+        fs::create_dir_all(&dest_dir)
+            .map_err(|e| Error::io_at(&dest_dir, e))?;
+
+        let package_name = request.file_name.as_deref()
+            .or_else(|| url.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| Error::Parse(format!("Invalid URL: {}", url)))?;
+        let package_path = dest_dir.join(package_name);
+
+        // 拿到许可后才创建临时文件，排队等待的下载不占用文件描述符
+        let _permits = self.acquire(url).await;
+        // 边下载边计算校验和，先写入临时文件，校验通过后再改名，
+        // 避免大文件（例如 NDK）整个驻留在内存中，也避免留下不完整的文件
+        let partial_path = dest_dir.join(format!("{}.partial", package_name));
+        let mut file = fs::File::create(&partial_path)
+            .map_err(|e| Error::io_at(&partial_path, e))?;
+        let mut hasher = request.checksum.hasher();
+        let streamed = self.stream(url, observer, |chunk| {
+            hasher.update(chunk);
+            file.write_all(chunk).map_err(|e| Error::io_at(&partial_path, e))
+        }).await;
+        let bytes = match streamed.and_then(|bytes| {
+            request.checksum.verify(hasher, url)?;
+            Ok(bytes)
+        }) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = fs::remove_file(&partial_path);
+                return Err(e);
+            }
+        };
+        observer.on_event(&ProgressEvent::Verified { file: url.to_string(), algorithm: request.checksum.algorithm() });
+
+        drop(file);
+        fs::rename(&partial_path, &package_path)
+            .map_err(|e| Error::io_at(&package_path, e))?;
+
+        Ok(DownloadedFile {
+            url: url.to_string(),
+            path: package_path,
+            bytes,
+        })
+    }
+
+    /// 在并发限制下下载多个文件
+    /// 
+    /// 返回值与 `requests` 一一对应，单个文件失败不影响其他文件。
+    pub async fn download_all(&self, requests: Vec<DownloadRequest>, observer: &dyn ProgressObserver) -> Vec<Result<DownloadedFile>> {
+        let futures = requests.iter().map(|request| self.download(request, observer));
+        futures::future::join_all(futures).await
+    }
+}
+
+/// 传输速度低于停滞检测阈值时的错误
+fn stalled(url: &str, bytes: u64, elapsed: Duration, stall: StallDetection) -> Error {
+    Error::Timeout {
+        url: url.to_string(),
+        reason: format!(
+            "transfer stalled: {} bytes in {:.0?}, below {} bytes/s",
+            bytes, elapsed, stall.min_bytes_per_sec
+        ),
+    }
+}
+
+/// 将相对路径转换为基于当前工作目录的绝对路径
+fn absolute_dir(dir: &Path) -> Result<PathBuf> {
+    if dir.is_absolute() {
+        Ok(dir.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(dir))
+    }
+}
 
 /// 异步下载单个包
 /// 
+/// 这是 [`DownloadManager::download`] 的便捷封装，每次调用都会创建新的 HTTP 客户端。
+/// 需要下载多个文件时应直接使用 [`DownloadManager`]。
+/// 
 /// # 参数
 /// 
 /// * `url` - 包的下载 URL
 /// * `root_dir` - 下载目标目录
 /// * `expected_md5` - 预期的 MD5 校验和
+/// * `observer` - 接收下载进度的观察者
 /// 
 /// # 返回值
 /// 
-/// 成功返回 `Ok(())`，失败返回包含错误信息的 `Err(String)`
+/// 成功返回已下载文件的信息，失败返回 [`Error`]
 /// 
 /// # 错误
 /// 
@@ -61,83 +416,32 @@ use tokio::io::AsyncWriteExt;
 /// - 网络错误
 /// - 文件系统错误
 /// - MD5 校验失败
-pub async fn download_package(url: String, root_dir: PathBuf, expected_md5: String) -> Result<(), String> {
-    // 获取当前工作目录
-    let current_dir = std::env::current_dir()
-        .map_err(|e| format!("Failed to get current directory: {}", e))?;
-    
-    // 使用绝对路径创建目标目录
-    let absolute_root_dir = if root_dir.is_absolute() {
-        root_dir
-    } else {
-        current_dir.join(root_dir)
-    };
-
-    fs::create_dir_all(&absolute_root_dir)
-        .map_err(|e| format!("Failed to create directory: {}", e))?;
-
-    let client = Client::builder()
-        .user_agent("Debian APT-HTTP/1.3 (2.0.9)")
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-    let response = client.get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!("Status: {} {}", response.status(), response.status().canonical_reason().unwrap_or("")));
-    }
-
-    let package_name = url.split('/').last()
-        .ok_or_else(|| "Invalid URL".to_string())?;
-    let package_path = absolute_root_dir.join(package_name);
-
-    let content = response.bytes()
-        .await
-        .map_err(|e| format!("Failed to get response content: {}", e))?;
-
-    // 计算下载内容的 MD5
-    let actual_md5 = format!("{:x}", md5::compute(&content));
-
-    // 验证 MD5
-    if actual_md5 != expected_md5 {
-        return Err(format!("MD5 checksum mismatch. Expected: {}, got: {}", expected_md5, actual_md5));
-    }
-
-    // 异步写入文件
-    let mut file = tokio::fs::File::create(&package_path)
-        .await
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    file.write_all(&content)
-        .await
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    println!("MD5 checksum verified successfully");
-    Ok(())
+pub async fn download_package(url: String, root_dir: PathBuf, expected_md5: String, observer: &dyn ProgressObserver) -> Result<DownloadedFile> {
+    let manager = DownloadManager::new(DownloadLimits::default())?;
+    manager.download(&DownloadRequest::new(url, root_dir, expected_md5), observer).await
 }
 
 /// 并行下载多个包
 /// 
+/// 使用默认并发限制的 [`DownloadManager`]，所有下载共享同一个 HTTP 客户端。
+/// 
 /// # 参数
 /// 
 /// * `downloads` - 包含 (URL, 目标目录, MD5) 元组的向量
+/// * `observer` - 接收下载进度的观察者
 /// 
 /// # 返回值
 /// 
-/// 成功返回 `Ok(())`，失败返回包含错误信息的 `Err(String)`
+/// 成功返回所有已下载文件的信息，失败返回 [`Error`]
 /// 
 /// # 错误
 /// 
 /// 如果任何一个包下载失败，整个操作都会失败
-pub async fn download_packages(downloads: Vec<(String, PathBuf, String)>) -> Result<(), String> {
-    let futures = downloads.into_iter().map(|(url, root_dir, md5)| {
-        download_package(url, root_dir, md5)
-    });
-
-    futures::future::try_join_all(futures)
-        .await
-        .map(|_| ())
-        .map_err(|e| format!("Failed to download packages: {}", e))
-} 
\ No newline at end of file
+pub async fn download_packages(downloads: Vec<(String, PathBuf, String)>, observer: &dyn ProgressObserver) -> Result<Vec<DownloadedFile>> {
+    let manager = DownloadManager::new(DownloadLimits::default())?;
+    let requests = downloads.into_iter()
+        .map(|(url, root_dir, md5)| DownloadRequest::new(url, root_dir, md5))
+        .collect();
+
+    manager.download_all(requests, observer).await.into_iter().collect()
+}
@@ -0,0 +1,208 @@
+//! # 依赖说明模块
+//!
+//! 这个模块回答"为什么安装了这个包"：在已安装的包之间沿 `Pre-Depends`、`Depends` 和
+//! `Recommends` 查找一条从顶层包到目标包的最短依赖链，类似 `aptitude why`。与 APT 的默认
+//! 设置一样，`Recommends` 视为需要，`Suggests` 不算。
+//!
+//! 顶层包由调用方给出，通常是用户明确请求安装的包（见 [`extended_states`](super::extended_states)）；
+//! 没有这些信息时可以用 [`top_level`]，即没有被其他已安装的包依赖的包。
+//!
+//! [`orphans`] 反过来找出不被任何顶层包需要的包，即 `autoremove` 可以删除的包。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::explain::{top_level, why};
+//! use mini_apt::package::package_info::parse_packages_file;
+//!
+//! let installed = parse_packages_file("\
+//! Package: hello
+//! Version: 2.10-2
+//! Architecture: arm64
+//! Depends: libc6 (>= 2.17)
+//! Filename: pool/main/h/hello/hello_2.10-2_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//!
+//! Package: libc6
+//! Version: 2.31-0ubuntu9
+//! Architecture: arm64
+//! Filename: pool/main/g/glibc/libc6_2.31-0ubuntu9_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//! ");
+//! let roots = top_level(&installed, "arm64");
+//! assert_eq!(roots.len(), 1);
+//! let libc6 = installed.find("libc6", "arm64").unwrap();
+//! let chain = why(&installed, &roots, libc6, "arm64").unwrap();
+//! assert_eq!(chain[0].to_string(), "hello 2.10-2 (arm64) Depends: libc6 (>= 2.17)");
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use super::extended_states::ExtendedStates;
+use super::index::PackageIndex;
+use super::multiarch::effective_architecture;
+use super::relation::{format_alternatives, Alternatives};
+use super::resolver::satisfies;
+use super::PackageInfo;
+
+/// 依赖链中的一步：`package` 的 `field` 字段中的 `dependency` 由链中的下一个包满足
+#[derive(Debug, Clone, Copy)]
+pub struct Link<'a> {
+    /// 声明依赖的包
+    pub package: &'a PackageInfo,
+    /// 依赖所在的字段，例如 `Depends`
+    pub field: &'static str,
+    /// 依赖（包括所有候选项）
+    pub dependency: &'a Alternatives,
+}
+
+impl fmt::Display for Link<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}) {}: {}",
+            self.package.package,
+            self.package.version,
+            self.package.architecture,
+            self.field,
+            format_alternatives(self.dependency)
+        )
+    }
+}
+
+/// 没有被其他已安装的包依赖的包
+///
+/// # 参数
+///
+/// * `installed` - 已安装的包
+/// * `native` - 本机（主）架构
+pub fn top_level<'a>(installed: &'a PackageIndex, native: &str) -> Vec<&'a PackageInfo> {
+    let mut required: HashSet<*const PackageInfo> = HashSet::new();
+    for package in installed.iter() {
+        for (_, group) in required_fields(package) {
+            for dependency in satisfying(installed, package, group, native) {
+                if !std::ptr::eq(dependency, package) {
+                    required.insert(dependency);
+                }
+            }
+        }
+    }
+    installed.iter().filter(|&package| !required.contains(&(package as *const _))).collect()
+}
+
+/// 从顶层包到目标包的最短依赖链
+///
+/// # 参数
+///
+/// * `installed` - 已安装的包
+/// * `roots` - 顶层包
+/// * `target` - 目标包
+/// * `native` - 本机（主）架构
+///
+/// # 返回值
+///
+/// 依赖链，目标包本身是顶层包时为空；没有任何顶层包依赖目标包时返回 `None`
+pub fn why<'a>(
+    installed: &'a PackageIndex,
+    roots: &[&'a PackageInfo],
+    target: &PackageInfo,
+    native: &str,
+) -> Option<Vec<Link<'a>>> {
+    let is_target = |p: &PackageInfo| p.package == target.package && p.architecture == target.architecture;
+    // 广度优先搜索，记录到达每个包的上一步
+    let mut previous: HashMap<*const PackageInfo, Link<'a>> = HashMap::new();
+    let mut queue: VecDeque<&'a PackageInfo> = roots.iter().copied().collect();
+    let mut visited: HashSet<*const PackageInfo> = roots.iter().map(|&p| p as *const _).collect();
+
+    while let Some(package) = queue.pop_front() {
+        if is_target(package) {
+            let mut chain = Vec::new();
+            let mut current: *const PackageInfo = package;
+            while let Some(link) = previous.get(&current) {
+                chain.push(*link);
+                current = link.package;
+            }
+            chain.reverse();
+            return Some(chain);
+        }
+        for (field, group) in required_fields(package) {
+            for next in satisfying(installed, package, group, native) {
+                if visited.insert(next) {
+                    previous.insert(next, Link { package, field, dependency: group });
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// 不被任何顶层包（直接或间接）需要的已安装包，按安装顺序排列
+///
+/// # 参数
+///
+/// * `installed` - 已安装的包
+/// * `roots` - 顶层包，通常是手动安装的包
+/// * `native` - 本机（主）架构
+pub fn orphans<'a>(installed: &'a PackageIndex, roots: &[&'a PackageInfo], native: &str) -> Vec<&'a PackageInfo> {
+    let mut required: HashSet<*const PackageInfo> = roots.iter().map(|&p| p as *const _).collect();
+    let mut stack: Vec<&'a PackageInfo> = roots.to_vec();
+    while let Some(package) = stack.pop() {
+        for (_, group) in required_fields(package) {
+            for next in satisfying(installed, package, group, native) {
+                if required.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    installed.iter().filter(|&package| !required.contains(&(package as *const _))).collect()
+}
+
+/// 没有标记为自动安装的包
+///
+/// # 参数
+///
+/// * `installed` - 已安装的包
+/// * `states` - 安装标记
+/// * `native` - 本机（主）架构
+pub fn manual_packages<'a>(installed: &'a PackageIndex, states: &ExtendedStates, native: &str) -> Vec<&'a PackageInfo> {
+    installed
+        .iter()
+        .filter(|p| !states.is_auto(&p.package, effective_architecture(&p.architecture, native)))
+        .collect()
+}
+
+/// `autoremove` 可以删除的包：不被任何手动安装的包需要的自动安装的包，按安装顺序排列
+///
+/// 手动安装的包本身不会出现在结果中。没有任何标记时所有包都视为手动安装，结果为空。
+///
+/// # 参数
+///
+/// * `installed` - 已安装的包
+/// * `states` - 安装标记
+/// * `native` - 本机（主）架构
+pub fn autoremovable<'a>(installed: &'a PackageIndex, states: &ExtendedStates, native: &str) -> Vec<&'a PackageInfo> {
+    orphans(installed, &manual_packages(installed, states, native), native)
+}
+
+/// 视为需要的关系：`Pre-Depends`、`Depends` 和 `Recommends`
+fn required_fields(package: &PackageInfo) -> impl Iterator<Item = (&'static str, &Alternatives)> {
+    let recommends = package.recommends.iter().map(|group| ("Recommends", group));
+    package.dependency_fields().chain(recommends)
+}
+
+/// 满足 `package` 的一组候选项的已安装包
+fn satisfying<'a>(installed: &'a PackageIndex, package: &PackageInfo, group: &Alternatives, native: &str) -> Vec<&'a PackageInfo> {
+    let arch = effective_architecture(&package.architecture, native);
+    group
+        .iter()
+        .flat_map(|dep| installed.get(&dep.name).chain(installed.providers(&dep.name)))
+        .filter(|&p| group.iter().any(|dep| satisfies(dep, arch, p, native)))
+        .collect()
+}
@@ -0,0 +1,159 @@
+//! # 自动安装标记
+//!
+//! 这个模块以 APT 的格式记录哪些包是作为依赖自动安装的：
+//! `var/lib/apt/extended_states` 中每个自动安装的包有一个条目，
+//!
+//! ```text
+//! Package: libc6
+//! Architecture: arm64
+//! Auto-Installed: 1
+//! ```
+//!
+//! 没有条目的已安装包视为手动安装，与 `apt-mark` 一致。`Architecture: all` 的包按主架构记录。
+//! 自动安装的包不再被任何手动安装的包（沿 `Pre-Depends`、`Depends` 和 `Recommends`）需要时，
+//! 可以由 `autoremove` 删除，见 [`orphans`](super::explain::orphans)。
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::package::extended_states::{ExtendedStates, Mark};
+//! use std::path::Path;
+//!
+//! ExtendedStates::update(Path::new("sysroot"), |states| {
+//!     states.set("libc6", "arm64", Mark::Auto);
+//!     Ok(())
+//! }).unwrap();
+//! let states = ExtendedStates::load(Path::new("sysroot")).unwrap();
+//! assert!(states.is_auto("libc6", "arm64"));
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::control::{parse_stanzas, Stanza};
+use super::status::write_atomic;
+use crate::error::{Error, Result};
+
+/// 标记文件，相对于安装根目录
+pub const EXTENDED_STATES_FILE: &str = "var/lib/apt/extended_states";
+
+/// 串行化对标记文件的读-改-写
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 包的安装方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// 作为依赖自动安装
+    Auto,
+    /// 用户明确请求安装
+    Manual,
+}
+
+/// 自动安装标记
+#[derive(Debug, Clone)]
+pub struct ExtendedStates {
+    root_dir: PathBuf,
+    entries: Vec<Stanza>,
+}
+
+impl ExtendedStates {
+    /// 读取安装根目录中的标记，文件不存在时所有包都视为手动安装
+    pub fn load(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(EXTENDED_STATES_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => parse_stanzas(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::io_at(path, e)),
+        };
+        Ok(Self {
+            root_dir: root_dir.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// 在锁内读取、修改并保存标记
+    pub fn update<T>(root_dir: &Path, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _guard = UPDATE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut states = Self::load(root_dir)?;
+        let result = f(&mut states)?;
+        states.save()?;
+        Ok(result)
+    }
+
+    /// 是否还没有任何包被标记为自动安装，例如标记功能出现之前安装的 sysroot
+    pub fn is_empty(&self) -> bool {
+        !self.entries.iter().any(is_auto_entry)
+    }
+
+    /// 包是否被标记为自动安装
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 包名
+    /// * `architecture` - 架构，`all` 的包使用主架构
+    pub fn is_auto(&self, name: &str, architecture: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| matches(entry, name, architecture) && is_auto_entry(entry))
+    }
+
+    /// 包的安装方式
+    pub fn mark(&self, name: &str, architecture: &str) -> Mark {
+        if self.is_auto(name, architecture) {
+            Mark::Auto
+        } else {
+            Mark::Manual
+        }
+    }
+
+    /// 设置包的安装方式，标记为手动安装时删除条目
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 包名
+    /// * `architecture` - 架构，`all` 的包使用主架构
+    /// * `mark` - 安装方式
+    pub fn set(&mut self, name: &str, architecture: &str, mark: Mark) {
+        self.forget(name, architecture);
+        if mark == Mark::Auto {
+            let mut entry = Stanza::default();
+            entry.set("Package", name);
+            entry.set("Architecture", architecture);
+            entry.set("Auto-Installed", "1");
+            self.entries.push(entry);
+            self.entries.sort_by(|a, b| {
+                (a.get("Package"), a.get("Architecture")).cmp(&(b.get("Package"), b.get("Architecture")))
+            });
+        }
+    }
+
+    /// 删除包的条目，用于包被删除之后
+    ///
+    /// 与标记为手动安装的结果相同，但不表示用户请求过这个包；之后重新作为依赖安装时
+    /// 会再次标记为自动安装。
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 包名
+    /// * `architecture` - 架构，`all` 的包使用主架构
+    pub fn forget(&mut self, name: &str, architecture: &str) {
+        self.entries.retain(|entry| !matches(entry, name, architecture));
+    }
+
+    /// 保存标记文件
+    pub fn save(&self) -> Result<()> {
+        let content: String = self.entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        write_atomic(&self.root_dir.join(EXTENDED_STATES_FILE), content.as_bytes())
+    }
+}
+
+/// 条目是否属于指定的包，没有 `Architecture` 字段的旧条目匹配所有架构
+fn matches(entry: &Stanza, name: &str, architecture: &str) -> bool {
+    entry.get("Package") == Some(name) && entry.get("Architecture").is_none_or(|arch| arch == architecture)
+}
+
+/// 条目是否标记为自动安装
+fn is_auto_entry(entry: &Stanza) -> bool {
+    entry.get("Auto-Installed") == Some("1")
+}
@@ -0,0 +1,423 @@
+//! # 归档解包模块
+//!
+//! 这个模块负责把下载的归档文件解包到安装根目录。
+//!
+//! 解包时会检查每个条目的路径，拒绝绝对路径和包含 `..` 的路径；写入之前还会解析
+//! 条目所在目录中的符号链接（包括同一个归档中先解出的链接），拒绝解析到目标目录之外的
+//! 条目，保证归档内容不会被写到目标目录之外。指向目标目录之内的链接（例如 usrmerge 的
+//! `lib -> usr/lib`）不受影响。
+//!
+//! ## 支持的格式
+//!
+//! - `zip`
+//! - `tar`
+//! - `tar.gz`（`tgz`）
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::package::extract::{extract_archive, ArchiveType};
+//! use std::path::Path;
+//!
+//! let archive = Path::new("vendor-sdk-4.2.0.tar.gz");
+//! let kind = ArchiveType::detect("vendor-sdk-4.2.0.tar.gz");
+//! assert_eq!(kind, ArchiveType::TarGz);
+//! let entries = extract_archive(archive, kind, Path::new("sysroot/opt/vendor-sdk"), 1).unwrap();
+//! println!("{} entries", entries);
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// Unix 文件类型掩码
+const S_IFMT: u32 = 0o170000;
+/// Unix 符号链接类型
+const S_IFLNK: u32 = 0o120000;
+
+/// 归档类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveType {
+    /// 未压缩的 tar
+    Tar,
+    /// gzip 压缩的 tar
+    TarGz,
+    /// zip
+    Zip,
+    /// 普通文件，不解包
+    File,
+}
+
+impl ArchiveType {
+    /// 根据文件名后缀推断归档类型，无法识别时视为普通文件
+    pub fn detect(file_name: &str) -> Self {
+        let name = file_name.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Self::TarGz
+        } else if name.ends_with(".tar") {
+            Self::Tar
+        } else if name.ends_with(".zip") {
+            Self::Zip
+        } else {
+            Self::File
+        }
+    }
+
+    /// 类型名称，与 [`FromStr`] 接受的写法一致
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tar => "tar",
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+            Self::File => "file",
+        }
+    }
+}
+
+impl FromStr for ArchiveType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "tar" => Ok(Self::Tar),
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            "zip" => Ok(Self::Zip),
+            "file" | "none" => Ok(Self::File),
+            other => Err(Error::Parse(format!("unsupported archive type: {}", other))),
+        }
+    }
+}
+
+impl std::fmt::Display for ArchiveType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 去掉路径的前 `strip` 个组成部分，并检查路径是否安全
+///
+/// 返回 `None` 表示条目应被跳过（路径被完全去掉）。
+pub(crate) fn sanitize_path(path: &Path, strip: usize) -> Result<Option<PathBuf>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => components.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(Error::Parse(format!(
+                    "unsafe path in archive: {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+    if components.len() <= strip {
+        return Ok(None);
+    }
+    Ok(Some(components[strip..].iter().collect()))
+}
+
+/// 检查 `path` 解析符号链接后仍在 `root` 之内
+///
+/// `path` 中不存在的部分之后会在最深的已存在的目录中创建，只检查已存在的部分。
+///
+/// # 参数
+///
+/// * `root` - 目标目录，已经过 [`fs::canonicalize`]
+/// * `path` - 要写入或创建的路径，以 `root` 开头
+pub(crate) fn ensure_inside(root: &Path, path: &Path) -> Result<()> {
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return Ok(()),
+        }
+    }
+    match fs::canonicalize(existing) {
+        Ok(resolved) if resolved.starts_with(root) => Ok(()),
+        _ => Err(Error::Parse(format!(
+            "unsafe path in archive: {} resolves outside {}",
+            path.display(),
+            root.display()
+        ))),
+    }
+}
+
+/// 创建目标目录并返回它解析符号链接后的路径
+fn canonical_dest(dest: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dest).map_err(|e| Error::io_at(dest, e))?;
+    fs::canonicalize(dest).map_err(|e| Error::io_at(dest, e))
+}
+
+/// 解包 zip 归档
+///
+/// 保留 Unix 权限位，并还原以 Unix 模式保存的符号链接（Android NDK 的 zip 包中
+/// 包含大量符号链接）。
+///
+/// # 参数
+///
+/// * `archive` - zip 文件路径
+/// * `dest` - 解包目标目录
+/// * `strip_components` - 去掉条目路径开头的目录层数
+///
+/// # 返回值
+///
+/// 成功返回解出的条目数
+///
+/// # 错误
+///
+/// 条目路径不安全，或者会经过先解出的符号链接写到 `dest` 之外时返回 [`Error::Parse`]
+///
+/// # 示例
+///
+/// ```rust
+/// use mini_apt::package::extract::extract_zip;
+/// use std::io::Write;
+///
+/// let dir = std::env::temp_dir().join("mini-apt-doctest-zip-symlink");
+/// let _ = std::fs::remove_dir_all(&dir);
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let outside = dir.join("outside");
+/// std::fs::create_dir_all(&outside).unwrap();
+///
+/// // 先放一个指向目标目录之外的链接，再通过它写文件
+/// let archive = dir.join("evil.zip");
+/// let mut zip = zip::ZipWriter::new(std::fs::File::create(&archive).unwrap());
+/// let options = zip::write::FileOptions::default();
+/// zip.add_symlink("foo", outside.to_str().unwrap(), options).unwrap();
+/// zip.start_file("foo/passwd", options).unwrap();
+/// zip.write_all(b"pwned").unwrap();
+/// zip.finish().unwrap();
+///
+/// let error = extract_zip(&archive, &dir.join("dest"), 0).unwrap_err();
+/// assert!(error.to_string().contains("resolves outside"));
+/// assert!(!outside.join("passwd").exists());
+/// ```
+pub fn extract_zip(archive: &Path, dest: &Path, strip_components: usize) -> Result<u64> {
+    let file = fs::File::open(archive).map_err(|e| Error::io_at(archive, e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| Error::Parse(format!("{}: {}", archive.display(), e)))?;
+    let root = canonical_dest(dest)?;
+
+    let mut entries = 0;
+    for index in 0..zip.len() {
+        let mut entry = zip
+            .by_index(index)
+            .map_err(|e| Error::Parse(format!("{}: {}", archive.display(), e)))?;
+        let name = PathBuf::from(entry.name());
+        let relative = match sanitize_path(&name, strip_components)? {
+            Some(relative) => relative,
+            None => continue,
+        };
+        let target = root.join(relative);
+
+        if entry.is_dir() {
+            ensure_inside(&root, &target)?;
+            fs::create_dir_all(&target).map_err(|e| Error::io_at(&target, e))?;
+            entries += 1;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            ensure_inside(&root, parent)?;
+            fs::create_dir_all(parent).map_err(|e| Error::io_at(parent, e))?;
+        }
+        remove_existing(&target)?;
+
+        let mode = entry.unix_mode();
+        if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+            let mut link = String::new();
+            io::Read::read_to_string(&mut entry, &mut link).map_err(|e| Error::io_at(&target, e))?;
+            create_symlink(&link, &target)?;
+        } else {
+            let mut out = fs::File::create(&target).map_err(|e| Error::io_at(&target, e))?;
+            io::copy(&mut entry, &mut out).map_err(|e| Error::io_at(&target, e))?;
+            if let Some(mode) = mode {
+                set_mode(&target, mode & 0o7777)?;
+            }
+        }
+        entries += 1;
+    }
+    Ok(entries)
+}
+
+/// 按类型解包归档
+///
+/// # 参数
+///
+/// * `archive` - 归档文件路径
+/// * `kind` - 归档类型
+/// * `dest` - 解包目标目录
+/// * `strip_components` - 去掉条目路径开头的目录层数
+///
+/// # 返回值
+///
+/// 成功返回解出的条目数。[`ArchiveType::File`] 会被复制到目标目录，返回 1。
+pub fn extract_archive(archive: &Path, kind: ArchiveType, dest: &Path, strip_components: usize) -> Result<u64> {
+    let open = || fs::File::open(archive).map_err(|e| Error::io_at(archive, e));
+    let result = match kind {
+        ArchiveType::Zip => return extract_zip(archive, dest, strip_components),
+        ArchiveType::Tar => extract_tar(io::BufReader::new(open()?), dest, strip_components),
+        ArchiveType::TarGz => extract_tar(flate2::read::GzDecoder::new(io::BufReader::new(open()?)), dest, strip_components),
+        ArchiveType::File => {
+            let name = archive
+                .file_name()
+                .ok_or_else(|| Error::Parse(format!("not a file: {}", archive.display())))?;
+            let target = dest.join(name);
+            if target != archive {
+                fs::create_dir_all(dest).map_err(|e| Error::io_at(dest, e))?;
+                fs::copy(archive, &target).map_err(|e| Error::io_at(&target, e))?;
+            }
+            return Ok(1);
+        }
+    };
+    result.map_err(|e| match e {
+        Error::Parse(message) => Error::Parse(format!("{}: {}", archive.display(), message)),
+        other => other,
+    })
+}
+
+/// 从读取器解包 tar 归档
+///
+/// 支持普通文件、目录、符号链接和硬链接，保留权限位；设备文件等其他条目会被忽略。
+/// 硬链接的目标同样会去掉开头的目录层数。
+///
+/// # 参数
+///
+/// * `reader` - 未压缩的 tar 数据流
+/// * `dest` - 解包目标目录
+/// * `strip_components` - 去掉条目路径开头的目录层数
+///
+/// # 返回值
+///
+/// 成功返回解出的条目数
+///
+/// # 错误
+///
+/// 条目路径不安全，或者会经过先解出的符号链接写到 `dest` 之外时返回 [`Error::Parse`]
+///
+/// # 示例
+///
+/// ```rust
+/// use mini_apt::package::extract::extract_tar;
+///
+/// let dir = std::env::temp_dir().join("mini-apt-doctest-tar-symlink");
+/// let _ = std::fs::remove_dir_all(&dir);
+/// let outside = dir.join("outside");
+/// std::fs::create_dir_all(&outside).unwrap();
+///
+/// // 先放一个指向目标目录之外的链接，再通过它写文件
+/// let mut builder = tar::Builder::new(Vec::new());
+/// let mut link = tar::Header::new_gnu();
+/// link.set_entry_type(tar::EntryType::Symlink);
+/// link.set_size(0);
+/// builder.append_link(&mut link, "foo", &outside).unwrap();
+/// let mut file = tar::Header::new_gnu();
+/// file.set_size(5);
+/// file.set_mode(0o644);
+/// builder.append_data(&mut file, "foo/passwd", &b"pwned"[..]).unwrap();
+/// let archive = builder.into_inner().unwrap();
+///
+/// let error = extract_tar(&archive[..], &dir.join("dest"), 0).unwrap_err();
+/// assert!(error.to_string().contains("resolves outside"));
+/// assert!(!outside.join("passwd").exists());
+/// ```
+pub fn extract_tar<R: io::Read>(reader: R, dest: &Path, strip_components: usize) -> Result<u64> {
+    extract_tar_files(reader, dest, strip_components).map(|files| files.len() as u64)
+}
+
+/// 从读取器解包 tar 归档，返回解出的条目相对于 `dest` 的路径
+///
+/// 与 [`extract_tar`] 相同，但保留条目列表，用于记录一个包安装了哪些文件。
+pub fn extract_tar_files<R: io::Read>(reader: R, dest: &Path, strip_components: usize) -> Result<Vec<PathBuf>> {
+    let invalid = |e: io::Error| Error::Parse(format!("invalid tar archive: {}", e));
+    let mut archive = tar::Archive::new(reader);
+    let root = canonical_dest(dest)?;
+
+    let mut files = Vec::new();
+    for entry in archive.entries().map_err(invalid)? {
+        let mut entry = entry.map_err(invalid)?;
+        let name = entry.path().map_err(invalid)?.into_owned();
+        let relative = match sanitize_path(&name, strip_components)? {
+            Some(relative) => relative,
+            None => continue,
+        };
+        let target = root.join(&relative);
+        let kind = entry.header().entry_type();
+        let mode = entry.header().mode().ok();
+
+        if kind.is_dir() {
+            // 目录保留默认权限，避免只读目录导致后续条目无法写入
+            ensure_inside(&root, &target)?;
+            fs::create_dir_all(&target).map_err(|e| Error::io_at(&target, e))?;
+            files.push(relative);
+            continue;
+        }
+        if !(kind.is_file() || kind.is_symlink() || kind.is_hard_link()) {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            ensure_inside(&root, parent)?;
+            fs::create_dir_all(parent).map_err(|e| Error::io_at(parent, e))?;
+        }
+        remove_existing(&target)?;
+
+        if kind.is_symlink() || kind.is_hard_link() {
+            let link = entry
+                .link_name()
+                .map_err(invalid)?
+                .ok_or_else(|| Error::Parse(format!("link without target: {}", name.display())))?
+                .into_owned();
+            if kind.is_symlink() {
+                create_symlink(&link.to_string_lossy(), &target)?;
+            } else {
+                let source = match sanitize_path(&link, strip_components)? {
+                    Some(source) => root.join(source),
+                    None => continue,
+                };
+                ensure_inside(&root, &source)?;
+                fs::hard_link(&source, &target).map_err(|e| Error::io_at(&target, e))?;
+            }
+        } else {
+            let mut out = fs::File::create(&target).map_err(|e| Error::io_at(&target, e))?;
+            io::copy(&mut entry, &mut out).map_err(|e| Error::io_at(&target, e))?;
+            if let Some(mode) = mode {
+                set_mode(&target, mode & 0o7777)?;
+            }
+        }
+        files.push(relative);
+    }
+    Ok(files)
+}
+
+/// 删除已存在的文件或符号链接，以便覆盖
+pub(crate) fn remove_existing(target: &Path) -> Result<()> {
+    match fs::symlink_metadata(target) {
+        Ok(metadata) if !metadata.is_dir() => fs::remove_file(target).map_err(|e| Error::io_at(target, e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn create_symlink(link: &str, target: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(link, target).map_err(|e| Error::io_at(target, e))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn create_symlink(link: &str, target: &Path) -> Result<()> {
+    // 不支持符号链接的平台上，把链接目标写成普通文件
+    fs::write(target, link).map_err(|e| Error::io_at(target, e))
+}
+
+#[cfg(unix)]
+pub(crate) fn set_mode(target: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(target, fs::Permissions::from_mode(mode)).map_err(|e| Error::io_at(target, e))
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_mode(_target: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,267 @@
+//! # 依赖图模块
+//!
+//! 这个模块提供审计 sysroot 所需的依赖关系查询：[`DependencyGraph`] 是解析结果的依赖图，
+//! 可以导出为 Graphviz DOT 或 JSON；[`reverse_dependencies`] 查找哪些包依赖某个包。
+//!
+//! 依赖图的节点是依赖闭包中的包，以及满足其中依赖的已安装包；每条边是一组
+//! `Pre-Depends` 或 `Depends` 候选项，指向满足它的包（优先依赖闭包中的包）。
+//! `Recommends` 和 `Suggests` 只有由依赖闭包中的包满足时才有对应的边。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::graph::DependencyGraph;
+//! use mini_apt::package::index::PackageIndex;
+//! use mini_apt::package::package_info::parse_packages_file;
+//! use mini_apt::package::resolver::{dependency_closure, ResolveOptions};
+//!
+//! let packages = parse_packages_file("\
+//! Package: hello
+//! Version: 2.10-2
+//! Architecture: arm64
+//! Depends: libc6 (>= 2.17)
+//! Filename: pool/main/h/hello/hello_2.10-2_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//!
+//! Package: libc6
+//! Version: 2.31-0ubuntu9
+//! Architecture: arm64
+//! Filename: pool/main/g/glibc/libc6_2.31-0ubuntu9_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//! ");
+//! let installed = PackageIndex::new();
+//! let hello = packages.find("hello", "arm64").unwrap();
+//! let closure = dependency_closure(&packages, hello, &installed, &ResolveOptions::new("arm64")).unwrap();
+//! let graph = DependencyGraph::build(&closure, &installed, "arm64");
+//! assert_eq!(graph.nodes.len(), 2);
+//! assert!(graph.to_dot().contains("\"hello:arm64\" -> \"libc6:arm64\""));
+//! assert!(graph.to_json().contains("\"relation\": \"libc6 (>= 2.17)\""));
+//! ```
+
+use std::fmt::Write;
+
+use super::index::PackageIndex;
+use super::multiarch::effective_architecture;
+use super::relation::format_alternatives;
+use super::resolver::satisfies;
+use super::PackageInfo;
+
+/// 依赖图的节点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// 包名
+    pub package: String,
+    /// 版本号
+    pub version: String,
+    /// 架构
+    pub architecture: String,
+    /// 是否已经安装
+    pub installed: bool,
+}
+
+impl Node {
+    /// 节点的标识，`包名:架构`
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.package, self.architecture)
+    }
+}
+
+/// 依赖图的边
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    /// 声明依赖的节点在 [`DependencyGraph::nodes`] 中的位置
+    pub from: usize,
+    /// 满足依赖的节点在 [`DependencyGraph::nodes`] 中的位置
+    pub to: usize,
+    /// 依赖所在的字段，例如 `Depends`
+    pub field: &'static str,
+    /// 依赖（包括所有候选项）
+    pub relation: String,
+}
+
+/// 依赖图
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    /// 节点，第一个是请求的包
+    pub nodes: Vec<Node>,
+    /// 边
+    pub edges: Vec<Edge>,
+}
+
+impl DependencyGraph {
+    /// 根据依赖闭包构建依赖图
+    ///
+    /// # 参数
+    ///
+    /// * `selected` - 依赖闭包，第一个是请求的包（见 [`dependency_closure`](super::resolver::dependency_closure)）
+    /// * `installed` - 已安装的包
+    /// * `native` - 本机（主）架构
+    pub fn build(selected: &[&PackageInfo], installed: &PackageIndex, native: &str) -> Self {
+        let mut graph = Self::default();
+        for package in selected {
+            graph.node(package, installed.contains(package));
+        }
+        for (from, package) in selected.iter().enumerate() {
+            let arch = effective_architecture(&package.architecture, native);
+            let hard = package.dependency_fields().map(|(field, group)| (field, group, false));
+            let soft = package.soft_dependency_fields().map(|(field, group)| (field, group, true));
+            for (field, group, soft) in hard.chain(soft) {
+                let satisfied_by = |p: &PackageInfo| group.iter().any(|dep| satisfies(dep, arch, p, native));
+                let to = match selected.iter().position(|p| satisfied_by(p)) {
+                    Some(to) => to,
+                    None if soft => continue,
+                    None => {
+                        let mut candidates = group
+                            .iter()
+                            .flat_map(|dep| installed.get(&dep.name).chain(installed.providers(&dep.name)));
+                        match candidates.find(|p| satisfied_by(p)) {
+                            Some(p) => graph.node(p, true),
+                            None => continue,
+                        }
+                    }
+                };
+                graph.edges.push(Edge { from, to, field, relation: format_alternatives(group) });
+            }
+        }
+        graph
+    }
+
+    /// 导出为 Graphviz DOT 格式
+    ///
+    /// 已安装的包以灰色显示，`Pre-Depends` 以粗线、`Recommends` 和 `Suggests` 以虚线显示。
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let style = if node.installed { ", style=filled, fillcolor=lightgrey" } else { "" };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\\n{}\"{}];",
+                dot_escape(&node.id()),
+                dot_escape(&node.id()),
+                dot_escape(&node.version),
+                style
+            );
+        }
+        for edge in &self.edges {
+            let style = match edge.field {
+                "Pre-Depends" => ", style=bold",
+                "Recommends" | "Suggests" => ", style=dashed",
+                _ => "",
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"{}];",
+                dot_escape(&self.nodes[edge.from].id()),
+                dot_escape(&self.nodes[edge.to].id()),
+                dot_escape(&edge.relation),
+                style
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 导出为 JSON
+    ///
+    /// 格式为 `{"nodes": [...], "edges": [...]}`，边的 `from`、`to` 是节点在 `nodes` 中的位置。
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                format!(
+                    "    {{\"package\": {}, \"version\": {}, \"architecture\": {}, \"installed\": {}}}",
+                    json_string(&node.package),
+                    json_string(&node.version),
+                    json_string(&node.architecture),
+                    node.installed
+                )
+            })
+            .collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                format!(
+                    "    {{\"from\": {}, \"to\": {}, \"field\": {}, \"relation\": {}}}",
+                    edge.from,
+                    edge.to,
+                    json_string(edge.field),
+                    json_string(&edge.relation)
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+
+    /// 加入节点（已存在时返回原有的位置）
+    fn node(&mut self, package: &PackageInfo, installed: bool) -> usize {
+        let existing = self.nodes.iter().position(|node| {
+            node.package == package.package && node.architecture == package.architecture && node.version == package.version
+        });
+        existing.unwrap_or_else(|| {
+            self.nodes.push(Node {
+                package: package.package.clone(),
+                version: package.version.clone(),
+                architecture: package.architecture.clone(),
+                installed,
+            });
+            self.nodes.len() - 1
+        })
+    }
+}
+
+/// 依赖 `target`（按包名或它 `Provides` 的虚包名）的所有包，按包名排序
+///
+/// # 参数
+///
+/// * `packages` - 查找范围，例如包索引或已安装的包
+/// * `target` - 被依赖的包
+pub fn reverse_dependencies<'a>(packages: &'a PackageIndex, target: &PackageInfo) -> Vec<&'a PackageInfo> {
+    let names: Vec<&str> = std::iter::once(target.package.as_str())
+        .chain(target.provides.iter().map(|provided| provided.name.as_str()))
+        .collect();
+    let mut result: Vec<&PackageInfo> = packages
+        .iter()
+        .filter(|package| {
+            package.dependency_fields().any(|(_, group)| group.iter().any(|dep| names.contains(&dep.name.as_str())))
+        })
+        .collect();
+    result.sort_by(|a, b| (&a.package, &a.architecture).cmp(&(&b.package, &b.architecture)));
+    result.dedup_by(|a, b| a.package == b.package && a.architecture == b.architecture);
+    result
+}
+
+/// DOT 字符串中的转义
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// JSON 字符串字面量
+fn json_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(result, "\\u{:04x}", c as u32);
+            }
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
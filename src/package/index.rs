@@ -0,0 +1,168 @@
+//! # 包索引模块
+//!
+//! [`PackageIndex`] 保存一个或多个 `Packages` 文件中的所有包，同一个包名可以有
+//! 多个架构和多个版本，例如同时启用 `arm64` 和 `armhf` 时的 `libc6`。
+//!
+//! 索引同时按 `Provides` 记录虚包的提供者，例如 `awk` 由 `mawk` 和 `gawk` 提供。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::package_info::parse_packages_file;
+//!
+//! let index = parse_packages_file("\
+//! Package: libc6
+//! Version: 2.31-0ubuntu9
+//! Architecture: arm64
+//! Multi-Arch: same
+//! Filename: pool/main/g/glibc/libc6_2.31-0ubuntu9_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//!
+//! Package: libc6
+//! Version: 2.31-0ubuntu9
+//! Architecture: armhf
+//! Multi-Arch: same
+//! Filename: pool/main/g/glibc/libc6_2.31-0ubuntu9_armhf.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//! ");
+//! assert_eq!(index.get("libc6").count(), 2);
+//! assert_eq!(index.find("libc6", "armhf").unwrap().architecture, "armhf");
+//!
+//! let index = parse_packages_file("\
+//! Package: mawk
+//! Version: 1.3.4
+//! Architecture: arm64
+//! Provides: awk
+//! Filename: pool/main/m/mawk/mawk_1.3.4_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//! ");
+//! assert_eq!(index.get("awk").count(), 0);
+//! assert_eq!(index.providers("awk").next().unwrap().package, "mawk");
+//! ```
+
+use std::collections::HashMap;
+
+use super::version::compare_versions;
+use super::PackageInfo;
+
+/// 包索引
+#[derive(Debug, Clone, Default)]
+pub struct PackageIndex {
+    packages: Vec<PackageInfo>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_provides: HashMap<String, Vec<usize>>,
+}
+
+impl PackageIndex {
+    /// 创建空索引
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入一个包，包名、架构和版本都相同的包会被替换
+    pub fn insert(&mut self, package: PackageInfo) {
+        let positions = self.by_name.entry(package.package.clone()).or_default();
+        let existing = positions.iter().copied().find(|&i| {
+            let other = &self.packages[i];
+            other.architecture == package.architecture && other.version == package.version
+        });
+        let position = match existing {
+            Some(i) => {
+                for provided in &self.packages[i].provides {
+                    if let Some(providers) = self.by_provides.get_mut(&provided.name) {
+                        providers.retain(|&p| p != i);
+                    }
+                }
+                self.packages[i] = package;
+                i
+            }
+            None => {
+                positions.push(self.packages.len());
+                self.packages.push(package);
+                self.packages.len() - 1
+            }
+        };
+        for provided in &self.packages[position].provides {
+            let providers = self.by_provides.entry(provided.name.clone()).or_default();
+            if !providers.contains(&position) {
+                providers.push(position);
+            }
+        }
+    }
+
+    /// 同名的所有包（所有架构和版本）
+    pub fn get<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a PackageInfo> + 'a {
+        self.by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(move |&i| &self.packages[i])
+    }
+
+    /// 所有包名（包括只通过 `Provides` 提供的虚包名），顺序不固定
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.by_name
+            .keys()
+            .chain(self.by_provides.iter().filter(|(_, providers)| !providers.is_empty()).map(|(name, _)| name))
+            .map(String::as_str)
+    }
+
+    /// 通过 `Provides` 提供 `name` 的所有包
+    pub fn providers<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a PackageInfo> + 'a {
+        self.by_provides
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(move |&i| &self.packages[i])
+    }
+
+    /// 查找指定架构（或 `Architecture: all`）的包，有多个版本时返回最高版本
+    pub fn find(&self, name: &str, architecture: &str) -> Option<&PackageInfo> {
+        self.get(name)
+            .filter(|p| p.architecture == architecture || p.architecture == "all")
+            .max_by(|a, b| compare_versions(&a.version, &b.version))
+    }
+
+    /// 是否包含包名、架构和版本都相同的包
+    pub fn contains(&self, package: &PackageInfo) -> bool {
+        self.get(&package.package)
+            .any(|p| p.architecture == package.architecture && p.version == package.version)
+    }
+
+    /// 所有包，按加入顺序排列
+    pub fn iter(&self) -> impl Iterator<Item = &PackageInfo> {
+        self.packages.iter()
+    }
+
+    /// 包的数量
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    /// 索引是否为空
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+impl Extend<PackageInfo> for PackageIndex {
+    fn extend<T: IntoIterator<Item = PackageInfo>>(&mut self, iter: T) {
+        for package in iter {
+            self.insert(package);
+        }
+    }
+}
+
+impl FromIterator<PackageInfo> for PackageIndex {
+    fn from_iter<T: IntoIterator<Item = PackageInfo>>(iter: T) -> Self {
+        let mut index = Self::new();
+        index.extend(iter);
+        index
+    }
+}
@@ -5,8 +5,25 @@
 //! ## 主要组件
 //! 
 //! - `PackageInfo`: 软件包信息结构
+//! - `architecture`: 架构名称、别名与 GNU 三元组
+//! - `checksum`: 校验和计算
+//! - `compression`: 压缩格式识别与解压
+//! - `control`: 控制文件（deb822）解析
+//! - `deb`: `.deb` 归档读取与安装
 //! - `downloader`: 包下载功能
+//! - `explain`: 依赖链说明（`why`）与孤立包查找
+//! - `extended_states`: 自动安装标记
+//! - `extract`: 归档解包
+//! - `graph`: 依赖图导出与反向依赖
+//! - `index`: 支持多架构、多版本的包索引
+//! - `multiarch`: Multi-Arch 规则
 //! - `package_info`: 包信息处理功能
+//! - `pdiff`: PDiff 增量索引更新
+//! - `relation`: 包关系（依赖）解析
+//! - `release`: 发行版 `Release` 文件解析
+//! - `resolver`: 依赖闭包计算
+//! - `status`: 安装状态数据库
+//! - `version`: Debian 版本号比较
 //! 
 //! ## 示例
 //! 
@@ -24,8 +41,28 @@
 //! );
 //! ```
 
+pub mod architecture;
+pub mod checksum;
+pub mod compression;
+pub mod control;
+pub mod deb;
 pub mod downloader;
+pub mod explain;
+pub mod extended_states;
+pub mod extract;
+pub mod graph;
+pub mod index;
+pub mod multiarch;
 pub mod package_info;
+pub mod pdiff;
+pub mod relation;
+pub mod release;
+pub mod resolver;
+pub mod status;
+pub mod version;
+
+use multiarch::MultiArch;
+use relation::{Alternatives, Dependency};
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -47,6 +84,26 @@ pub struct PackageInfo {
     pub md5sum: String,
     /// SHA256 校验和
     pub sha256: String,
+    /// 安装后占用的空间（KiB），来自 `Installed-Size` 字段
+    pub installed_size: Option<u64>,
+    /// `Multi-Arch` 字段
+    pub multi_arch: MultiArch,
+    /// `Pre-Depends` 字段
+    pub pre_depends: Vec<Alternatives>,
+    /// `Depends` 字段
+    pub depends: Vec<Alternatives>,
+    /// `Recommends` 字段
+    pub recommends: Vec<Alternatives>,
+    /// `Suggests` 字段
+    pub suggests: Vec<Alternatives>,
+    /// `Provides` 字段，即这个包提供的虚包
+    pub provides: Vec<Dependency>,
+    /// `Conflicts` 字段
+    pub conflicts: Vec<Dependency>,
+    /// `Breaks` 字段
+    pub breaks: Vec<Dependency>,
+    /// `Replaces` 字段
+    pub replaces: Vec<Dependency>,
 }
 
 impl PackageInfo {
@@ -86,6 +143,60 @@ impl PackageInfo {
             size,
             md5sum,
             sha256,
+            installed_size: None,
+            multi_arch: MultiArch::No,
+            pre_depends: Vec::new(),
+            depends: Vec::new(),
+            recommends: Vec::new(),
+            suggests: Vec::new(),
+            provides: Vec::new(),
+            conflicts: Vec::new(),
+            breaks: Vec::new(),
+            replaces: Vec::new(),
         }
     }
+
+    /// `Pre-Depends` 和 `Depends` 中的每组候选项，带字段名
+    pub fn dependency_fields(&self) -> impl Iterator<Item = (&'static str, &Alternatives)> {
+        let pre_depends = self.pre_depends.iter().map(|group| ("Pre-Depends", group));
+        pre_depends.chain(self.depends.iter().map(|group| ("Depends", group)))
+    }
+
+    /// `Recommends` 和 `Suggests` 中的每组候选项，带字段名
+    pub fn soft_dependency_fields(&self) -> impl Iterator<Item = (&'static str, &Alternatives)> {
+        let recommends = self.recommends.iter().map(|group| ("Recommends", group));
+        recommends.chain(self.suggests.iter().map(|group| ("Suggests", group)))
+    }
+
+    /// 包关系（不考虑架构）是否指向这个包或它 `Provides` 的虚包
+    ///
+    /// 用于 `Conflicts`、`Breaks` 和 `Replaces`。
+    pub fn matches_relation(&self, relation: &Dependency) -> bool {
+        (self.package == relation.name && relation.matches_version(&self.version))
+            || self.provides.iter().any(|provided| relation.is_provided_by(provided))
+    }
+
+    /// 标准的 `.deb` 文件名 `name_version_arch.deb`
+    ///
+    /// 与 `apt-get download` 一致，版本号中的 epoch 分隔符 `:` 写作 `%3a`。
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use mini_apt::package::PackageInfo;
+    ///
+    /// let package = PackageInfo::new(
+    ///     "cpp".to_string(),
+    ///     "4:9.3.0-1ubuntu2".to_string(),
+    ///     "arm64".to_string(),
+    ///     "pool/main/g/gcc-defaults/cpp_9.3.0-1ubuntu2_arm64.deb".to_string(),
+    ///     1024,
+    ///     String::new(),
+    ///     String::new(),
+    /// );
+    /// assert_eq!(package.deb_file_name(), "cpp_4%3a9.3.0-1ubuntu2_arm64.deb");
+    /// ```
+    pub fn deb_file_name(&self) -> String {
+        format!("{}_{}_{}.deb", self.package, self.version.replace(':', "%3a"), self.architecture)
+    }
 }
@@ -0,0 +1,123 @@
+//! # Multi-Arch 模块
+//!
+//! 这个模块实现 Debian 的 Multi-Arch 规则，决定不同架构的包之间能否互相满足依赖、
+//! 能否同时安装：
+//!
+//! - `Multi-Arch: same`: 不同架构的同名包可以同时安装，例如 `libc6:arm64` 和 `libc6:armhf`
+//! - `Multi-Arch: foreign`: 可以满足任何架构的包的依赖，例如 `make`、`perl-base`
+//! - `Multi-Arch: allowed`: 依赖写作 `name:any` 时可以满足任何架构的包的依赖
+//! - 没有声明（`no`）: 只能满足相同架构的包的依赖
+//!
+//! `Architecture: all` 的包在依赖关系中视为本机（主）架构。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::multiarch::{split_qualified_name, MultiArch};
+//!
+//! assert_eq!(split_qualified_name("libc6:armhf"), ("libc6", Some("armhf")));
+//! assert_eq!(split_qualified_name("make"), ("make", None));
+//! assert_eq!("foreign".parse::<MultiArch>().unwrap(), MultiArch::Foreign);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::relation::Dependency;
+use super::PackageInfo;
+use crate::error::{Error, Result};
+
+/// `Multi-Arch` 字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MultiArch {
+    /// 没有声明
+    #[default]
+    No,
+    /// 不同架构的同名包可以同时安装
+    Same,
+    /// 可以满足任何架构的包的依赖
+    Foreign,
+    /// 依赖写作 `name:any` 时可以满足任何架构的包的依赖
+    Allowed,
+}
+
+impl MultiArch {
+    /// 字段值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MultiArch::No => "no",
+            MultiArch::Same => "same",
+            MultiArch::Foreign => "foreign",
+            MultiArch::Allowed => "allowed",
+        }
+    }
+}
+
+impl FromStr for MultiArch {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "no" => Ok(MultiArch::No),
+            "same" => Ok(MultiArch::Same),
+            "foreign" => Ok(MultiArch::Foreign),
+            "allowed" => Ok(MultiArch::Allowed),
+            other => Err(Error::Parse(format!("invalid Multi-Arch value: {}", other))),
+        }
+    }
+}
+
+impl fmt::Display for MultiArch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 拆分 `name[:arch]` 形式的包名
+pub fn split_qualified_name(name: &str) -> (&str, Option<&str>) {
+    match name.split_once(':') {
+        Some((name, arch)) => (name, Some(arch)),
+        None => (name, None),
+    }
+}
+
+/// 包在依赖关系中的架构，`all` 视为本机架构 `native`
+pub fn effective_architecture<'a>(architecture: &'a str, native: &'a str) -> &'a str {
+    if architecture == "all" {
+        native
+    } else {
+        architecture
+    }
+}
+
+/// 候选包的架构能否满足依赖，不比较包名和版本
+///
+/// # 参数
+///
+/// * `dependency` - 依赖
+/// * `dependent_architecture` - 声明依赖的包的架构
+/// * `candidate` - 候选包
+/// * `native` - 本机架构
+pub fn satisfies_architecture(dependency: &Dependency, dependent_architecture: &str, candidate: &PackageInfo, native: &str) -> bool {
+    let dependent = effective_architecture(dependent_architecture, native);
+    let candidate_arch = effective_architecture(&candidate.architecture, native);
+    match dependency.architecture.as_deref() {
+        None => candidate_arch == dependent || candidate.multi_arch == MultiArch::Foreign,
+        Some("any") => {
+            candidate_arch == dependent || matches!(candidate.multi_arch, MultiArch::Foreign | MultiArch::Allowed)
+        }
+        Some("native") => candidate_arch == native,
+        Some(arch) => candidate_arch == arch,
+    }
+}
+
+/// 两个同名包能否同时安装
+///
+/// 相同架构的同名包（即同一个包的不同版本）不能同时安装；不同架构时两个包都必须是
+/// `Multi-Arch: same`。
+pub fn co_installable(a: &PackageInfo, b: &PackageInfo, native: &str) -> bool {
+    a.package != b.package
+        || (effective_architecture(&a.architecture, native) != effective_architecture(&b.architecture, native)
+            && a.multi_arch == MultiArch::Same
+            && b.multi_arch == MultiArch::Same)
+}
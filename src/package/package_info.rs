@@ -11,39 +11,103 @@
 //! ## 示例
 //! 
 //! ```rust,no_run
-//! use mini_apt::package::package_info::{download_packages_file, parse_packages_file, find_package};
+//! use mini_apt::package::architecture::Architecture;
+//! use mini_apt::package::package_info::{download_packages_file, parse_packages_file, find_package, IndexTarget};
+//! use mini_apt::progress::SilentObserver;
 //! 
 //! #[tokio::main]
-//! async fn main() -> Result<(), String> {
+//! async fn main() -> Result<(), mini_apt::error::Error> {
 //!     let mirror = "https://mirrors.tuna.tsinghua.edu.cn/ubuntu-ports";
-//!     let arch = "arm64";
+//!     let arch = Architecture::Arm64;
+//!     let components = ["main".to_string(), "universe".to_string()];
+//!     let target = IndexTarget::new(mirror, "focal", &components, &[arch]);
 //! 
 //!     // 下载包信息
-//!     let content = download_packages_file(mirror, arch).await?;
+//!     let content = download_packages_file(&target, &SilentObserver).await?;
 //! 
 //!     // 解析包信息
 //!     let packages = parse_packages_file(&content);
 //! 
 //!     // 查找特定包
-//!     if let Some(package) = find_package(&packages, "cpp-x86-64-linux-gnu", arch) {
+//!     if let Some(package) = find_package(&packages, "cpp-x86-64-linux-gnu", arch.as_str()) {
 //!         println!("Found package: {} version {}", package.package, package.version);
 //!     }
 //!     Ok(())
 //! }
 //! ```
 
-use std::collections::HashMap;
 use std::io::Read;
-use reqwest::Client;
-
+use std::path::{Path, PathBuf};
+use super::architecture::Architecture;
+use super::compression::Compression;
+use super::downloader::{DownloadLimits, DownloadManager};
+use super::control::{parse_stanzas, Stanza};
+use super::index::PackageIndex;
+use super::pdiff::update_index;
+use super::release::{fetch_release, FreshnessPolicy, Release, ReleaseFile};
+use super::status::write_atomic;
+use super::relation::parse_relations;
 use super::PackageInfo;
+use crate::error::{Error, Result};
+use crate::progress::{ProgressEvent, ProgressObserver};
+
+/// 索引缓存目录，相对于安装根目录
+pub const LISTS_DIR: &str = "var/lib/apt/lists";
+
+/// 索引文件的压缩格式，按优先顺序排列
+pub const INDEX_COMPRESSIONS: [Compression; 3] = [Compression::Xz, Compression::Gzip, Compression::None];
+
+/// 要下载的仓库索引
+#[derive(Debug, Clone)]
+pub struct IndexTarget {
+    /// 镜像站 URL
+    pub mirror: String,
+    /// 发行版代号，例如 `focal`
+    pub suite: String,
+    /// 仓库组件，例如 `main`、`universe`
+    pub components: Vec<String>,
+    /// 目标架构，每个架构下载一份 `binary-<架构>/Packages`
+    pub architectures: Vec<Architecture>,
+    /// 缓存解压后索引的目录，为 `None` 时不缓存
+    ///
+    /// 有缓存时，与 `Release` 一致的索引不再下载，仓库提供 PDiff 补丁时增量更新。
+    pub lists_dir: Option<PathBuf>,
+    /// 只读取缓存，不写入新的索引和 `Release`，用于不应修改安装根目录的安装计划和查询
+    ///
+    /// 此时缓存的 `Release` 仍用于拒绝回滚，但不会更新为新的基准。
+    pub read_only_cache: bool,
+    /// `Release` 文件的有效期检查
+    pub freshness: FreshnessPolicy,
+}
+
+impl IndexTarget {
+    /// 创建不使用缓存、使用默认有效期检查的索引目标
+    pub fn new(mirror: &str, suite: &str, components: &[String], architectures: &[Architecture]) -> Self {
+        Self {
+            mirror: mirror.to_string(),
+            suite: suite.to_string(),
+            components: components.to_vec(),
+            architectures: architectures.to_vec(),
+            lists_dir: None,
+            read_only_cache: false,
+            freshness: FreshnessPolicy::default(),
+        }
+    }
+
+    /// 发行版目录的 URL
+    pub(crate) fn base_url(&self) -> String {
+        format!("{}/dists/{}", self.mirror, self.suite)
+    }
+}
 
 /// 从镜像站下载包信息文件
 /// 
+/// 这是 [`fetch_packages_file`] 的便捷封装，使用默认配置的下载管理器。
+/// 
 /// # 参数
 /// 
-/// * `mirror` - 镜像站 URL
-/// * `arch` - 目标架构
+/// * `target` - 要下载的仓库索引
+/// * `observer` - 接收下载进度的观察者
 /// 
 /// # 返回值
 /// 
@@ -55,123 +119,293 @@ use super::PackageInfo;
 /// - 网络错误
 /// - 解压错误
 /// - 无效的响应
-pub async fn download_packages_file(mirror: &str, arch: &str) -> Result<String, String> {
-    // 尝试不同的仓库组件
-    let components = ["main", "universe"];
-    let mut all_content = String::new();
+pub async fn download_packages_file(target: &IndexTarget, observer: &dyn ProgressObserver) -> Result<String> {
+    let manager = DownloadManager::new(DownloadLimits::default())?;
+    fetch_packages_file(&manager, target, observer).await
+}
 
-    let client = Client::builder()
-        .user_agent("Debian APT-HTTP/1.3 (2.0.9)")
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-    for component in components {
-        let url = format!("{}/dists/focal/{}/binary-{}/Packages.gz", mirror, component, arch);
-        println!("Trying to download from: {}", url);
-        
-        match client.get(&url).send().await {
-            Ok(response) if response.status().is_success() => {
-                match response.bytes().await {
-                    Ok(bytes) => {
-                        // 解压 gzip 数据
-                        let mut decoder = flate2::read::GzDecoder::new(&bytes[..]);
-                        let mut content = String::new();
-                        if decoder.read_to_string(&mut content).is_ok() {
-                            println!("Successfully downloaded {} repository information", component);
-                            all_content.push_str(&content);
-                            all_content.push('\n');
-                        } else {
-                            println!("Failed to decompress {} repository information", component);
-                        }
-                    }
-                    Err(e) => println!("Failed to read response from {}: {}", component, e),
-                }
-            }
-            Ok(response) => println!("Failed to download {} repository: {}", component, response.status()),
-            Err(e) => println!("Failed to download {} repository: {}", component, e),
+/// 通过下载管理器从镜像站下载包信息文件
+/// 
+/// 先下载并检查发行版的 `Release` 文件（见 [`fetch_release`]），按其中列出的文件为每个组件选择索引的压缩格式，
+/// 依次尝试 [`INDEX_COMPRESSIONS`] 中当前构建支持的格式，下载失败时换下一种，
+/// 并用 `Release` 中的校验和校验下载的内容。镜像站没有 `Release` 文件（并且没有缓存过）时
+/// 直接依次尝试各种格式。
+/// 仓库支持 `Acquire-By-Hash` 时每种格式先尝试 `by-hash` 路径，再尝试普通路径。
+/// 
+/// 设置了 [`IndexTarget::lists_dir`] 时，缓存的索引与 `Release` 一致则直接使用，
+/// 否则先尝试用 PDiff 补丁增量更新（见 [`pdiff`](super::pdiff)），失败时再完整下载。
+/// 
+/// 各个仓库组件和架构的索引并行下载，并受下载管理器的并发限制约束。
+/// 
+/// # 参数
+/// 
+/// * `manager` - 下载管理器
+/// * `target` - 要下载的仓库索引
+/// * `observer` - 接收下载进度的观察者
+/// 
+/// # 错误
+/// 
+/// 所有组件的索引都无法获取时返回错误，`Release` 未通过检查时返回 [`Error::InvalidRelease`]
+pub async fn fetch_packages_file(manager: &DownloadManager, target: &IndexTarget, observer: &dyn ProgressObserver) -> Result<String> {
+    let release = match fetch_release(manager, target, observer).await {
+        Ok(release) => Some(release),
+        // 无效的 Release，或者缓存过却下载不到的 Release，都不能退回到不校验的下载
+        Err(e @ Error::InvalidRelease { .. }) => {
+            observer.on_event(&ProgressEvent::Error { subject: format!("{}/Release", target.base_url()), message: e.to_string() });
+            return Err(e);
+        }
+        Err(e) => {
+            observer.on_event(&ProgressEvent::Error {
+                subject: format!("{}/Release", target.base_url()),
+                message: e.to_string(),
+            });
+            None
         }
+    };
+
+    let paths = target.components.iter().flat_map(|component| {
+        target.architectures.iter().map(move |arch| format!("{}/binary-{}/Packages", component, arch))
+    });
+    let results = futures::future::join_all(paths.map(|path| {
+        fetch_component_index(manager, target, path, release.as_ref(), observer)
+    })).await;
+
+    // 失败的组件已经在下载时报告过
+    let mut all_content = String::new();
+    for content in results.into_iter().flatten() {
+        all_content.push_str(&content);
+        all_content.push('\n');
     }
 
     if all_content.is_empty() {
-        Err("Failed to download Packages.gz from any component".to_string())
+        Err(Error::NoMirror(format!("{}/*/binary-{{{}}}/Packages", target.base_url(), target.architectures.iter().map(Architecture::as_str).collect::<Vec<_>>().join(","))))
     } else {
         Ok(all_content)
     }
 }
 
-/// 解析包信息文件内容
-/// 
-/// # 参数
-/// 
-/// * `content` - 包信息文件的内容
-/// 
-/// # 返回值
-/// 
-/// 返回包名到包信息的映射
-pub fn parse_packages_file(content: &str) -> HashMap<String, PackageInfo> {
-    let mut packages = HashMap::new();
-    let mut current_package: Option<String> = None;
-    let mut current_info: HashMap<String, String> = HashMap::new();
-
-    for line in content.lines() {
-        if line.is_empty() {
-            // 空行表示一个包的信息结束
-            if let Some(package_name) = current_package.take() {
-                if let Ok(package_info) = create_package_info(&package_name, &current_info) {
-                    packages.insert(package_name, package_info);
+/// 获取一个组件的索引，优先使用缓存
+///
+/// `path` 是不带压缩后缀的索引路径，相对于发行版目录。每次失败都会报告给 `observer`。
+async fn fetch_component_index(
+    manager: &DownloadManager,
+    target: &IndexTarget,
+    path: String,
+    release: Option<&Release>,
+    observer: &dyn ProgressObserver,
+) -> Result<String> {
+    let base = target.base_url();
+    let url = format!("{}/{}", base, path);
+    let cache = target.lists_dir.as_ref().map(|dir| dir.join(list_file_name(&url)));
+    // 没有 Release 或 Release 中没有未压缩索引的校验和时无法判断缓存是否有效
+    let expected = release.and_then(|release| release.file(&path)).map(|file| &file.checksum);
+
+    if let (Some(cache), Some(expected), Some(release)) = (&cache, expected, release) {
+        if let Ok(cached) = std::fs::read_to_string(cache) {
+            if expected.verify_bytes(cached.as_bytes(), &url).is_ok() {
+                observer.on_event(&ProgressEvent::IndexFetchFinished { url, bytes: cached.len() as u64 });
+                return Ok(cached);
+            }
+            match update_index(manager, &base, &path, release, &cached, observer).await {
+                Ok(content) => {
+                    save_cache(target, cache, &content, observer);
+                    return Ok(content);
                 }
+                Err(e) => observer.on_event(&ProgressEvent::Error { subject: format!("{}.diff", url), message: e.to_string() }),
             }
-            current_info.clear();
-            continue;
         }
+    }
 
-        if line.starts_with(' ') {
-            // 继续上一行的值
-            continue;
-        }
+    let content = download_component_index(manager, &base, &path, release, observer).await?;
+    if let Some(cache) = &cache {
+        save_cache(target, cache, &content, observer);
+    }
+    Ok(content)
+}
 
-        if let Some((key, value)) = line.split_once(": ") {
-            if key == "Package" {
-                current_package = Some(value.to_string());
+/// 完整下载一个组件的索引，依次尝试可用的压缩格式
+async fn download_component_index(
+    manager: &DownloadManager,
+    base: &str,
+    path: &str,
+    release: Option<&Release>,
+    observer: &dyn ProgressObserver,
+) -> Result<String> {
+    let candidates: Vec<(String, Option<&ReleaseFile>)> = INDEX_COMPRESSIONS
+        .iter()
+        .filter(|compression| compression.is_supported())
+        .map(|compression| match compression {
+            Compression::None => path.to_string(),
+            other => format!("{}.{}", path, other.extension()),
+        })
+        .filter_map(|name| match release {
+            Some(release) => release.file(&name).map(|file| (name, Some(file))),
+            None => Some((name, None)),
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        let url = format!("{}/{}", base, path);
+        let message = "not listed in Release".to_string();
+        observer.on_event(&ProgressEvent::Error { subject: url.clone(), message: message.clone() });
+        return Err(Error::Parse(format!("{}: {}", url, message)));
+    }
+
+    let by_hash = release.is_some_and(Release::acquire_by_hash);
+    let urls = candidates.iter().flat_map(|(name, file)| {
+        let hashed = file.filter(|_| by_hash).and_then(|file| file.by_hash_path());
+        hashed
+            .into_iter()
+            .chain(std::iter::once(name.clone()))
+            .map(move |path| (format!("{}/{}", base, path), name, *file))
+    });
+
+    let mut last_error = None;
+    for (url, name, file) in urls {
+        observer.on_event(&ProgressEvent::IndexFetchStarted { url: url.clone() });
+        match fetch_index(manager, &url, Compression::from_file_name(name), file, observer).await {
+            Ok(content) => {
+                observer.on_event(&ProgressEvent::IndexFetchFinished { url, bytes: content.len() as u64 });
+                return Ok(content);
+            }
+            Err(e) => {
+                observer.on_event(&ProgressEvent::Error { subject: url, message: e.to_string() });
+                last_error = Some(e);
             }
-            current_info.insert(key.to_string(), value.to_string());
         }
     }
+    Err(last_error.expect("at least one candidate was tried"))
+}
 
-    // 处理最后一个包
-    if let Some(package_name) = current_package {
-        if let Ok(package_info) = create_package_info(&package_name, &current_info) {
-            packages.insert(package_name, package_info);
-        }
+/// 下载、校验并解压单个索引文件
+///
+/// `file` 为 `Release` 中对应的条目。
+async fn fetch_index(
+    manager: &DownloadManager,
+    url: &str,
+    compression: Compression,
+    file: Option<&ReleaseFile>,
+    observer: &dyn ProgressObserver,
+) -> Result<String> {
+    let bytes = manager.fetch(url, observer).await?;
+    if let Some(file) = file {
+        file.checksum.verify_bytes(&bytes, url)?;
     }
 
-    packages
+    let mut decoder = compression.decoder(&bytes[..])?;
+    let mut content = String::new();
+    decoder.read_to_string(&mut content)
+        .map_err(|e| Error::Parse(format!("Failed to decompress {}: {}", url, e)))?;
+    Ok(content)
+}
+
+/// 索引在缓存目录中的文件名
+///
+/// 与 apt 类似，去掉 URL 的协议部分并把 `/` 换成 `_`，例如
+/// `mirror.example.com_ubuntu_dists_focal_main_binary-arm64_Packages`。
+pub fn list_file_name(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    without_scheme.trim_matches('/').replace('/', "_")
 }
 
-/// 从原始信息创建包信息结构
+/// 写入缓存，失败只报告给 `observer`，不影响本次安装；缓存只读时不写入
+fn save_cache(target: &IndexTarget, path: &Path, content: &str, observer: &dyn ProgressObserver) {
+    if target.read_only_cache {
+        return;
+    }
+    if let Err(e) = write_atomic(path, content.as_bytes()) {
+        observer.on_event(&ProgressEvent::Error { subject: path.display().to_string(), message: e.to_string() });
+    }
+}
+
+/// 解析包信息文件内容
 /// 
 /// # 参数
 /// 
-/// * `package_name` - 包名
-/// * `info` - 包含包信息的键值对
+/// * `content` - 包信息文件的内容
 /// 
 /// # 返回值
 /// 
-/// 成功返回包信息结构，失败返回错误信息
-fn create_package_info(package_name: &str, info: &HashMap<String, String>) -> Result<PackageInfo, String> {
-    Ok(PackageInfo::new(
+/// 返回包含所有包的索引
+pub fn parse_packages_file(content: &str) -> PackageIndex {
+    parse_stanzas(content)
+        .iter()
+        .filter_map(|stanza| create_package_info(stanza).ok())
+        .collect()
+}
+
+/// 从控制文件段落创建包信息结构
+/// 
+/// # 参数
+/// 
+/// * `stanza` - `Packages` 文件中的一个段落
+/// 
+/// # 返回值
+/// 
+/// 成功返回包信息结构，缺少必需字段或字段值无效时返回 [`Error::Parse`]
+fn create_package_info(stanza: &Stanza) -> Result<PackageInfo> {
+    let package_name = stanza
+        .get("Package")
+        .ok_or_else(|| Error::Parse("stanza without Package field".to_string()))?;
+    let field = |key: &str| {
+        stanza.get(key)
+            .map(str::to_string)
+            .ok_or_else(|| Error::Parse(format!("{}: missing {}", package_name, key)))
+    };
+    let mut package = PackageInfo::new(
         package_name.to_string(),
-        info.get("Version").ok_or("Missing Version")?.to_string(),
-        info.get("Architecture").ok_or("Missing Architecture")?.to_string(),
-        info.get("Filename").ok_or("Missing Filename")?.to_string(),
-        info.get("Size").ok_or("Missing Size")?.parse().map_err(|_| "Invalid Size")?,
-        info.get("MD5sum").ok_or("Missing MD5sum")?.to_string(),
-        info.get("SHA256").ok_or("Missing SHA256")?.to_string(),
-    ))
+        field("Version")?,
+        field("Architecture")?,
+        field("Filename")?,
+        field("Size")?.parse().map_err(|_| Error::Parse(format!("{}: invalid Size", package_name)))?,
+        field("MD5sum")?,
+        field("SHA256")?,
+    );
+    read_control_fields(&mut package, stanza)?;
+    Ok(package)
+}
+
+/// 读取 `Packages` 文件和 `.deb` 控制文件共有的可选字段
+///
+/// 包括 `Installed-Size`、`Multi-Arch` 和各种包关系字段。
+pub(crate) fn read_control_fields(package: &mut PackageInfo, stanza: &Stanza) -> Result<()> {
+    let package_name = package.package.clone();
+    package.installed_size = stanza
+        .get("Installed-Size")
+        .map(|size| size.parse())
+        .transpose()
+        .map_err(|_| Error::Parse(format!("{}: invalid Installed-Size", package_name)))?;
+    package.multi_arch = stanza
+        .get("Multi-Arch")
+        .map(str::parse)
+        .transpose()
+        .map_err(|e| Error::Parse(format!("{}: {}", package_name, e)))?
+        .unwrap_or_default();
+    let relations = |key: &str| {
+        stanza.get(key)
+            .map(parse_relations)
+            .transpose()
+            .map(Option::unwrap_or_default)
+            .map_err(|e| match e {
+                Error::Parse(message) => Error::Parse(format!("{}: {}: {}", package_name, key, message)),
+                other => other,
+            })
+    };
+    package.pre_depends = relations("Pre-Depends")?;
+    package.depends = relations("Depends")?;
+    package.recommends = relations("Recommends")?;
+    package.suggests = relations("Suggests")?;
+    package.provides = relations("Provides")?.into_iter().flatten().collect();
+    package.conflicts = relations("Conflicts")?.into_iter().flatten().collect();
+    package.breaks = relations("Breaks")?.into_iter().flatten().collect();
+    package.replaces = relations("Replaces")?.into_iter().flatten().collect();
+    Ok(())
 }
 
 /// 在包集合中查找特定包
 /// 
+/// `Architecture: all` 的包对任何架构都能找到；有多个版本时返回最高版本。
+/// 
 /// # 参数
 /// 
 /// * `packages` - 包集合
@@ -181,6 +415,6 @@ fn create_package_info(package_name: &str, info: &HashMap<String, String>) -> Re
 /// # 返回值
 /// 
 /// 如果找到包则返回 Some(PackageInfo)，否则返回 None
-pub fn find_package<'a>(packages: &'a HashMap<String, PackageInfo>, name: &str, arch: &str) -> Option<&'a PackageInfo> {
-    packages.get(name).filter(|p| p.architecture == arch)
-} 
\ No newline at end of file
+pub fn find_package<'a>(packages: &'a PackageIndex, name: &str, arch: &str) -> Option<&'a PackageInfo> {
+    packages.find(name, arch)
+}
\ No newline at end of file
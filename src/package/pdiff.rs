@@ -0,0 +1,268 @@
+//! # PDiff 增量索引模块
+//!
+//! Debian 仓库在 `<组件>/binary-<架构>/Packages.diff/` 下为 `Packages` 文件提供增量补丁：
+//!
+//! - `Index`: 列出当前版本和历史版本的校验和，以及每个补丁的名称和校验和
+//! - `<补丁名>.gz`: `diff --ed` 格式的补丁
+//!
+//! 本地缓存的索引与某个历史版本相同时，只需下载之后的补丁并依次应用，
+//! 结果再用 `Release` 文件中的校验和校验。`X-Patch-Precedence: merged` 的仓库中
+//! 每个补丁直接从对应的历史版本更新到当前版本，只需应用一个补丁。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::pdiff::apply_ed_script;
+//!
+//! let mut lines = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+//! apply_ed_script(&mut lines, "3c\nC\n.\n1d\n").unwrap();
+//! assert_eq!(lines, ["b", "C"]);
+//! ```
+
+use std::io::Read;
+
+use super::checksum::Checksum;
+use super::compression::Compression;
+use super::control::parse_stanzas;
+use super::downloader::DownloadManager;
+use super::release::Release;
+use crate::error::{Error, Result};
+use crate::progress::{ProgressEvent, ProgressObserver};
+
+/// `Index` 文件中的一个条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    /// SHA-256（十六进制）
+    pub sha256: String,
+    /// 大小（字节）
+    pub size: u64,
+    /// 补丁名
+    pub name: String,
+}
+
+/// 解析后的 `Packages.diff/Index` 文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffIndex {
+    /// 当前版本的 SHA-256
+    pub current: String,
+    /// 历史版本，每个条目是应用同名补丁之前的文件
+    pub history: Vec<DiffEntry>,
+    /// 解压后的补丁
+    pub patches: Vec<DiffEntry>,
+    /// 压缩后的补丁，没有列出时按 `<补丁名>.gz` 下载
+    pub download: Vec<DiffEntry>,
+    /// 每个补丁是否直接更新到当前版本
+    pub merged: bool,
+}
+
+impl DiffIndex {
+    /// 解析 `Index` 文件内容
+    ///
+    /// # 错误
+    ///
+    /// 缺少 `SHA256-Current` 字段或条目格式无效时返回 [`Error::Parse`]
+    pub fn parse(content: &str) -> Result<Self> {
+        let stanza = parse_stanzas(content)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Parse("empty Packages.diff/Index".to_string()))?;
+        let current = stanza
+            .get("SHA256-Current")
+            .and_then(|value| value.split_whitespace().next())
+            .ok_or_else(|| Error::Parse("Packages.diff/Index has no SHA256-Current".to_string()))?
+            .to_ascii_lowercase();
+        let entries = |field: &str| -> Result<Vec<DiffEntry>> {
+            stanza
+                .get(field)
+                .unwrap_or("")
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    [sha256, size, name] => Ok(DiffEntry {
+                        sha256: sha256.to_ascii_lowercase(),
+                        size: size
+                            .parse()
+                            .map_err(|_| Error::Parse(format!("invalid size in {}: {}", field, line.trim())))?,
+                        name: name.to_string(),
+                    }),
+                    _ => Err(Error::Parse(format!("invalid {} entry: {}", field, line.trim()))),
+                })
+                .collect()
+        };
+        Ok(Self {
+            current,
+            history: entries("SHA256-History")?,
+            patches: entries("SHA256-Patches")?,
+            download: entries("SHA256-Download")?,
+            merged: stanza.get("X-Patch-Precedence") == Some("merged"),
+        })
+    }
+
+    /// 把 SHA-256 为 `sha256` 的文件更新到当前版本需要依次应用的补丁
+    ///
+    /// 文件已经是当前版本时返回空列表，不是任何历史版本时返回 `None`。
+    pub fn patches_for(&self, sha256: &str) -> Option<Vec<&DiffEntry>> {
+        if sha256 == self.current {
+            return Some(Vec::new());
+        }
+        let start = self.history.iter().position(|entry| entry.sha256 == sha256)?;
+        let names = if self.merged {
+            &self.history[start..=start]
+        } else {
+            &self.history[start..]
+        };
+        names
+            .iter()
+            .map(|entry| self.patches.iter().find(|patch| patch.name == entry.name))
+            .collect()
+    }
+
+    /// 补丁的下载文件名和压缩后的 SHA-256
+    fn download_for(&self, name: &str) -> (String, Option<&str>) {
+        let compressed = |entry: &&DiffEntry| entry.name.strip_prefix(name).is_some_and(|ext| ext.starts_with('.'));
+        match self.download.iter().find(compressed) {
+            Some(entry) => (entry.name.clone(), Some(entry.sha256.as_str())),
+            None => (format!("{}.gz", name), None),
+        }
+    }
+}
+
+/// 把 `diff --ed` 格式的补丁应用到按行分割的文件内容上
+///
+/// 支持 `a`（追加）、`c`（替换）和 `d`（删除）命令，命令必须按行号从大到小排列，
+/// 这也是 `diff --ed` 的输出顺序。内容中只有一个 `.` 的行按 `diff --ed` 的方式处理：
+/// 先输入 `..`，结束输入后用 `s/.//` 去掉多余的 `.`，再用不带行号的 `a` 继续追加。
+///
+/// # 错误
+///
+/// 命令无效或行号超出范围时返回 [`Error::Parse`]
+pub fn apply_ed_script(lines: &mut Vec<String>, script: &str) -> Result<()> {
+    let mut script = script.lines();
+    // ed 的当前行（从 1 开始），`s/.//` 和不带行号的 `a` 作用于这一行
+    let mut current = lines.len();
+    while let Some(command) = script.next() {
+        if command.is_empty() {
+            continue;
+        }
+        let invalid = || Error::Parse(format!("invalid ed command: {}", command));
+        if command == "s/.//" {
+            let line = current.checked_sub(1).and_then(|index| lines.get_mut(index)).ok_or_else(invalid)?;
+            if !line.starts_with('.') {
+                return Err(invalid());
+            }
+            line.remove(0);
+            continue;
+        }
+        // 命令来自网络，最后一个字符可能是多字节字符
+        let (split, _) = command.char_indices().last().ok_or_else(invalid)?;
+        let (range, op) = command.split_at(split);
+        let (first, last) = if range.is_empty() && op == "a" {
+            (current, current)
+        } else {
+            let (first, last) = range.split_once(',').unwrap_or((range, range));
+            (first.parse::<usize>().map_err(|_| invalid())?, last.parse::<usize>().map_err(|_| invalid())?)
+        };
+        if first > last || last > lines.len() || (op != "a" && first == 0) {
+            return Err(invalid());
+        }
+
+        let mut text = Vec::new();
+        if op == "a" || op == "c" {
+            loop {
+                match script.next() {
+                    Some(".") => break,
+                    Some(line) => text.push(line.to_string()),
+                    None => return Err(Error::Parse(format!("unterminated text for ed command: {}", command))),
+                }
+            }
+        }
+        let inserted = text.len();
+        match op {
+            "a" => {
+                lines.splice(last..last, text);
+                current = last + inserted;
+            }
+            "c" => {
+                lines.splice(first - 1..last, text);
+                current = first - 1 + inserted;
+            }
+            "d" => {
+                lines.drain(first - 1..last);
+                current = first.min(lines.len());
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(())
+}
+
+/// 用 PDiff 补丁把缓存的索引更新到 `Release` 文件描述的版本
+///
+/// # 参数
+///
+/// * `manager` - 下载管理器
+/// * `base` - 发行版目录的 URL，例如 `http://mirror/ubuntu/dists/focal`
+/// * `path` - 未压缩索引的路径，相对于发行版目录，例如 `main/binary-arm64/Packages`
+/// * `release` - 发行版的 `Release` 文件
+/// * `cached` - 缓存的索引内容
+/// * `observer` - 接收下载进度的观察者
+///
+/// # 错误
+///
+/// 仓库没有提供补丁、缓存的版本太旧、下载失败或结果与 `Release` 不一致时返回错误，
+/// 调用方应改为完整下载索引
+pub async fn update_index(
+    manager: &DownloadManager,
+    base: &str,
+    path: &str,
+    release: &Release,
+    cached: &str,
+    observer: &dyn ProgressObserver,
+) -> Result<String> {
+    let expected = match release.file(path).map(|file| &file.checksum) {
+        Some(checksum @ Checksum::Sha256(_)) => checksum,
+        _ => return Err(Error::Parse(format!("{}: no SHA256 in Release", path))),
+    };
+    let index_path = format!("{}.diff/Index", path);
+    let index_file = release
+        .file(&index_path)
+        .ok_or_else(|| Error::Parse(format!("{}: not listed in Release", index_path)))?;
+
+    let url = format!("{}/{}", base, index_path);
+    observer.on_event(&ProgressEvent::IndexFetchStarted { url: url.clone() });
+    let bytes = manager.fetch(&url, observer).await?;
+    index_file.checksum.verify_bytes(&bytes, &url)?;
+    let index = DiffIndex::parse(&String::from_utf8_lossy(&bytes))?;
+    observer.on_event(&ProgressEvent::IndexFetchFinished { url, bytes: bytes.len() as u64 });
+
+    let cached_hash = Checksum::sha256_of_bytes(cached.as_bytes());
+    let patches = index
+        .patches_for(cached_hash.hex())
+        .ok_or_else(|| Error::Parse(format!("{}: cached index is not in the patch history", path)))?;
+
+    let mut lines: Vec<String> = cached.lines().map(str::to_string).collect();
+    for patch in patches {
+        let (file_name, download_hash) = index.download_for(&patch.name);
+        let url = format!("{}/{}.diff/{}", base, path, file_name);
+        observer.on_event(&ProgressEvent::IndexFetchStarted { url: url.clone() });
+        let bytes = manager.fetch(&url, observer).await?;
+        if let Some(hash) = download_hash {
+            Checksum::Sha256(hash.to_string()).verify_bytes(&bytes, &url)?;
+        }
+        let mut script = String::new();
+        Compression::from_file_name(&file_name)
+            .decoder(&bytes[..])?
+            .read_to_string(&mut script)
+            .map_err(|e| Error::Parse(format!("Failed to decompress {}: {}", url, e)))?;
+        Checksum::Sha256(patch.sha256.clone()).verify_bytes(script.as_bytes(), &url)?;
+        apply_ed_script(&mut lines, &script)?;
+        observer.on_event(&ProgressEvent::IndexFetchFinished { url, bytes: bytes.len() as u64 });
+    }
+
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    expected.verify_bytes(content.as_bytes(), &format!("{}/{}", base, path))?;
+    Ok(content)
+}
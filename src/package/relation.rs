@@ -0,0 +1,179 @@
+//! # 包关系模块
+//!
+//! 这个模块解析 `Depends`、`Pre-Depends`、`Provides` 等包关系字段。
+//!
+//! 字段由逗号分隔的依赖组成，每个依赖可以是用 `|` 分隔的多个候选项，
+//! 每个候选项形如 `name[:arch] [(relation version)]`。源码包中才会出现的
+//! `[架构限制]` 和 `<构建配置>` 会被忽略。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::relation::parse_relations;
+//!
+//! let depends = parse_relations("libc6 (>= 2.17), mawk | awk, python3:any").unwrap();
+//! assert_eq!(depends.len(), 3);
+//! assert_eq!(depends[0][0].name, "libc6");
+//! assert!(depends[0][0].matches_version("2.31-0ubuntu9"));
+//! assert_eq!(depends[1].len(), 2);
+//! assert_eq!(depends[2][0].architecture.as_deref(), Some("any"));
+//! ```
+
+use std::fmt;
+
+use super::version::{Relation, VersionConstraint};
+use crate::error::{Error, Result};
+
+/// 依赖的一个候选项
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dependency {
+    /// 包名
+    pub name: String,
+    /// 架构限定，例如 `python3:any` 中的 `any`
+    pub architecture: Option<String>,
+    /// 版本约束
+    pub constraint: Option<VersionConstraint>,
+}
+
+impl Dependency {
+    /// 创建没有架构限定和版本约束的依赖
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            architecture: None,
+            constraint: None,
+        }
+    }
+
+    /// 版本号是否满足该依赖的版本约束
+    pub fn matches_version(&self, version: &str) -> bool {
+        self.constraint.as_ref().is_none_or(|c| c.matches(version))
+    }
+
+    /// `Provides` 中的一项能否满足该依赖，不考虑架构
+    ///
+    /// 没有版本约束的依赖可以由任何同名的 `Provides` 满足；有版本约束时，只有写作
+    /// `name (= version)` 且版本满足约束的 `Provides` 才能满足。
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use mini_apt::package::relation::parse_relations;
+    ///
+    /// let provides = parse_relations("libc-dev, libc6-dev (= 2.31-0ubuntu9)").unwrap();
+    /// let depends = parse_relations("libc-dev, libc-dev (>= 2.17), libc6-dev (>= 2.17)").unwrap();
+    /// assert!(depends[0][0].is_provided_by(&provides[0][0]));
+    /// assert!(!depends[1][0].is_provided_by(&provides[0][0]));
+    /// assert!(depends[2][0].is_provided_by(&provides[1][0]));
+    /// ```
+    pub fn is_provided_by(&self, provided: &Dependency) -> bool {
+        if self.name != provided.name {
+            return false;
+        }
+        match (&self.constraint, &provided.constraint) {
+            (None, _) => true,
+            (Some(constraint), Some(version)) if version.relation == Relation::Equal => {
+                constraint.matches(&version.version)
+            }
+            _ => false,
+        }
+    }
+
+    /// 解析单个候选项
+    fn parse(text: &str) -> Result<Self> {
+        let invalid = || Error::Parse(format!("invalid relation: {}", text));
+        let text = strip_restrictions(text);
+        let (name_part, constraint) = match text.split_once('(') {
+            Some((name, rest)) => {
+                let inner = rest.trim_end().strip_suffix(')').ok_or_else(invalid)?.trim();
+                let split = inner
+                    .find(|c: char| !matches!(c, '<' | '>' | '='))
+                    .ok_or_else(invalid)?;
+                let (relation, version) = inner.split_at(split);
+                let version = version.trim();
+                if version.is_empty() {
+                    return Err(invalid());
+                }
+                (name.trim(), Some(VersionConstraint::new(relation.trim().parse()?, version)))
+            }
+            None => (text.trim(), None),
+        };
+        let (name, architecture) = match name_part.split_once(':') {
+            Some((name, arch)) => (name, Some(arch.to_string())),
+            None => (name_part, None),
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            architecture,
+            constraint,
+        })
+    }
+}
+
+impl fmt::Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(arch) = &self.architecture {
+            write!(f, ":{}", arch)?;
+        }
+        if let Some(constraint) = &self.constraint {
+            write!(f, " ({})", constraint)?;
+        }
+        Ok(())
+    }
+}
+
+/// 一组候选项，满足其中任意一个即可
+pub type Alternatives = Vec<Dependency>;
+
+/// 以 `a | b` 的形式输出一组候选项
+pub fn format_alternatives(alternatives: &[Dependency]) -> String {
+    alternatives.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(" | ")
+}
+
+/// 去掉 `[架构限制]` 和 `<构建配置>`，版本约束括号内的 `<` 保持不变
+fn strip_restrictions(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_version = false;
+    let mut closing = None;
+    for c in text.chars() {
+        if let Some(end) = closing {
+            if c == end {
+                closing = None;
+            }
+            continue;
+        }
+        match c {
+            '(' => in_version = true,
+            ')' => in_version = false,
+            '[' if !in_version => {
+                closing = Some(']');
+                continue;
+            }
+            '<' if !in_version => {
+                closing = Some('>');
+                continue;
+            }
+            _ => {}
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 解析包关系字段
+///
+/// # 错误
+///
+/// 候选项格式无效时返回 [`Error::Parse`]
+pub fn parse_relations(field: &str) -> Result<Vec<Alternatives>> {
+    field
+        .split(',')
+        .map(str::trim)
+        .filter(|group| !group.is_empty())
+        .map(|group| group.split('|').map(Dependency::parse).collect())
+        .collect()
+}
@@ -0,0 +1,315 @@
+//! # Release 文件模块
+//!
+//! 每个发行版目录（`dists/<suite>/`）下的 `Release` 文件描述了这个发行版的元数据，
+//! 并列出所有索引文件的路径、大小和校验和。这个模块解析 `Release` 文件，
+//! 下载索引时据此选择镜像站实际提供的压缩格式，并校验下载的内容。
+//!
+//! 声明了 `Acquire-By-Hash: yes` 的仓库还在 `by-hash/SHA256/<校验和>` 下按内容保存索引，
+//! 镜像站同步到一半时旧的 `Release` 文件仍然能找到与之匹配的索引。
+//!
+//! 下载的 `Release` 文件按 [`FreshnessPolicy`] 检查 `Date` 和 `Valid-Until`，
+//! 并与缓存的版本比较，拒绝过期、尚未生效或比缓存更旧的文件，
+//! 避免过时或被重放的镜像站悄悄提供旧的索引。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::release::Release;
+//!
+//! let release = Release::parse("\
+//! Suite: focal
+//! Components: main universe
+//! SHA256:
+//!  8bb4f0f2e5e7e3a4f2a0b4a6a1b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7 1234 main/binary-arm64/Packages.xz
+//! ").unwrap();
+//! assert_eq!(release.get("Suite"), Some("focal"));
+//! assert_eq!(release.file("main/binary-arm64/Packages.xz").unwrap().size, 1234);
+//! assert!(release.file("main/binary-arm64/Packages.gz").is_none());
+//! ```
+
+use std::fs;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::checksum::Checksum;
+use super::control::{parse_stanzas, Stanza};
+use super::downloader::DownloadManager;
+use super::package_info::{list_file_name, IndexTarget};
+use super::status::write_atomic;
+use crate::error::{Error, Result};
+use crate::progress::{ProgressEvent, ProgressObserver};
+
+/// `Release` 文件中列出的一个索引文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseFile {
+    /// 相对于发行版目录的路径，例如 `main/binary-arm64/Packages.xz`
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 文件的校验和
+    pub checksum: Checksum,
+}
+
+impl ReleaseFile {
+    /// 按内容寻址的路径 `<目录>/by-hash/<算法>/<校验和>`，相对于发行版目录
+    ///
+    /// 只有 SHA256 校验和有对应的路径。
+    ///
+    /// ```rust
+    /// use mini_apt::package::checksum::Checksum;
+    /// use mini_apt::package::release::ReleaseFile;
+    ///
+    /// let file = ReleaseFile {
+    ///     path: "main/binary-arm64/Packages.xz".to_string(),
+    ///     size: 1234,
+    ///     checksum: Checksum::Sha256("ab12".to_string()),
+    /// };
+    /// assert_eq!(file.by_hash_path().unwrap(), "main/binary-arm64/by-hash/SHA256/ab12");
+    /// ```
+    pub fn by_hash_path(&self) -> Option<String> {
+        let hash = match &self.checksum {
+            Checksum::Sha256(hash) => hash,
+            _ => return None,
+        };
+        Some(match self.path.rsplit_once('/') {
+            Some((dir, _)) => format!("{}/by-hash/SHA256/{}", dir, hash),
+            None => format!("by-hash/SHA256/{}", hash),
+        })
+    }
+}
+
+/// 解析后的 `Release` 文件
+#[derive(Debug, Clone, Default)]
+pub struct Release {
+    fields: Stanza,
+    files: Vec<ReleaseFile>,
+}
+
+impl Release {
+    /// 解析 `Release` 文件内容
+    ///
+    /// 文件列表优先取 `SHA256` 字段，没有时取 `MD5Sum` 字段。
+    ///
+    /// # 错误
+    ///
+    /// 内容为空或文件列表格式无效时返回 [`Error::Parse`]
+    pub fn parse(content: &str) -> Result<Self> {
+        let fields = parse_stanzas(content)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Parse("empty Release file".to_string()))?;
+        let (list, checksum): (_, fn(String) -> Checksum) = match (fields.get("SHA256"), fields.get("MD5Sum")) {
+            (Some(list), _) => (list, Checksum::Sha256),
+            (None, Some(list)) => (list, Checksum::Md5),
+            (None, None) => ("", Checksum::Sha256),
+        };
+
+        let mut files = Vec::new();
+        for line in list.lines().filter(|line| !line.trim().is_empty()) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (hash, size, path) = match parts.as_slice() {
+                [hash, size, path] => (hash, size, path),
+                _ => return Err(Error::Parse(format!("invalid Release file entry: {}", line.trim()))),
+            };
+            files.push(ReleaseFile {
+                path: path.to_string(),
+                size: size
+                    .parse()
+                    .map_err(|_| Error::Parse(format!("invalid size in Release file entry: {}", line.trim())))?,
+                checksum: checksum(hash.to_ascii_lowercase()),
+            });
+        }
+        Ok(Self { fields, files })
+    }
+
+    /// 读取字段值，字段名不区分大小写
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields.get(name)
+    }
+
+    /// `Date` 字段：生成这个 `Release` 文件的时间
+    ///
+    /// # 错误
+    ///
+    /// 字段不是 RFC 2822 格式的时间时返回 [`Error::Parse`]
+    pub fn date(&self) -> Result<Option<DateTime<Utc>>> {
+        self.get("Date").map(parse_date).transpose()
+    }
+
+    /// `Valid-Until` 字段：这个 `Release` 文件的过期时间
+    ///
+    /// # 错误
+    ///
+    /// 字段不是 RFC 2822 格式的时间时返回 [`Error::Parse`]
+    pub fn valid_until(&self) -> Result<Option<DateTime<Utc>>> {
+        self.get("Valid-Until").map(parse_date).transpose()
+    }
+
+    /// 仓库是否支持按内容寻址下载索引（`Acquire-By-Hash: yes`）
+    pub fn acquire_by_hash(&self) -> bool {
+        self.get("Acquire-By-Hash").is_some_and(|value| value.eq_ignore_ascii_case("yes"))
+    }
+
+    /// 列出的所有索引文件
+    pub fn files(&self) -> &[ReleaseFile] {
+        &self.files
+    }
+
+    /// 查找列出的索引文件
+    pub fn file(&self, path: &str) -> Option<&ReleaseFile> {
+        self.files.iter().find(|file| file.path == path)
+    }
+}
+
+/// `Release` 文件的有效期检查
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreshnessPolicy {
+    /// 是否检查 `Valid-Until` 字段，快照镜像站等不再更新的仓库需要关闭
+    pub check_valid_until: bool,
+    /// 从 `Date` 算起的最长有效时间，比 `Valid-Until` 更早或没有 `Valid-Until` 时以此为准
+    pub max_age: Option<Duration>,
+    /// 允许的本机与镜像站之间的时钟误差
+    pub clock_skew: Duration,
+}
+
+impl Default for FreshnessPolicy {
+    /// 检查 `Valid-Until`，不限制最长有效时间，允许 5 分钟的时钟误差
+    fn default() -> Self {
+        Self {
+            check_valid_until: true,
+            max_age: None,
+            clock_skew: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl FreshnessPolicy {
+    /// 检查 `Release` 文件在 `now` 时是否有效
+    ///
+    /// # 错误
+    ///
+    /// `Date` 晚于当前时间、已经过期或时间字段无效时返回 [`Error::InvalidRelease`]
+    ///
+    /// ```rust
+    /// use chrono::{TimeZone, Utc};
+    /// use mini_apt::package::release::{FreshnessPolicy, Release};
+    ///
+    /// let release = Release::parse("\
+    /// Date: Thu, 23 Apr 2020 17:33:17 UTC
+    /// Valid-Until: Thu, 30 Apr 2020 17:33:17 UTC
+    /// ").unwrap();
+    /// let policy = FreshnessPolicy::default();
+    /// assert!(policy.check(&release, "Release", Utc.with_ymd_and_hms(2020, 4, 24, 0, 0, 0).unwrap()).is_ok());
+    /// assert!(policy.check(&release, "Release", Utc.with_ymd_and_hms(2020, 5, 1, 0, 0, 0).unwrap()).is_err());
+    /// ```
+    pub fn check(&self, release: &Release, url: &str, now: DateTime<Utc>) -> Result<()> {
+        let reject = |reason: String| Error::InvalidRelease { url: url.to_string(), reason };
+        let skew = chrono::Duration::from_std(self.clock_skew).unwrap_or(chrono::Duration::MAX);
+        let date = release.date().map_err(|e| reject(e.to_string()))?;
+        if let Some(date) = date {
+            if date > now + skew {
+                return Err(reject(format!("not valid yet (Date is {})", format_date(date))));
+            }
+        }
+
+        let mut until = if self.check_valid_until {
+            release.valid_until().map_err(|e| reject(e.to_string()))?
+        } else {
+            None
+        };
+        if let (Some(max_age), Some(date)) = (self.max_age, date) {
+            let limit = date + chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+            until = Some(until.map_or(limit, |until| until.min(limit)));
+        }
+        match until {
+            Some(until) if now > until + skew => Err(reject(format!("expired on {}", format_date(until)))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 下载、检查并解析镜像站上一个发行版的 `Release` 文件
+///
+/// 文件先按 [`IndexTarget::freshness`] 检查有效期。设置了 [`IndexTarget::lists_dir`] 时，
+/// 还与缓存的 `Release` 比较 `Date`，比缓存更旧时发出警告并拒绝使用；通过检查后更新缓存
+/// （[`IndexTarget::read_only_cache`] 时不更新）。
+///
+/// 已经缓存过 `Release` 的仓库不再接受没有 `Release` 的情况：镜像站隐藏或损坏这个文件
+/// 不能绕过回滚和有效期检查。
+///
+/// # 参数
+///
+/// * `manager` - 下载管理器
+/// * `target` - 要下载的仓库索引
+/// * `observer` - 接收下载进度的观察者
+///
+/// # 错误
+///
+/// 内容无法解析、未通过检查，或者已有缓存的 `Release` 但下载失败时返回
+/// [`Error::InvalidRelease`]；没有缓存时下载失败返回下载的错误
+pub async fn fetch_release(
+    manager: &DownloadManager,
+    target: &IndexTarget,
+    observer: &dyn ProgressObserver,
+) -> Result<Release> {
+    let url = format!("{}/Release", target.base_url());
+    observer.on_event(&ProgressEvent::IndexFetchStarted { url: url.clone() });
+    let cache = target.lists_dir.as_ref().map(|dir| dir.join(list_file_name(&url)));
+    let bytes = match manager.fetch(&url, observer).await {
+        Ok(bytes) => bytes,
+        Err(e) if cache.as_ref().is_some_and(|cache| cache.is_file()) => {
+            return Err(Error::InvalidRelease {
+                url,
+                reason: format!("a Release is cached but the mirror no longer provides one ({})", e),
+            });
+        }
+        Err(e) => return Err(e),
+    };
+    let release = Release::parse(&String::from_utf8_lossy(&bytes))
+        .map_err(|e| Error::InvalidRelease { url: url.clone(), reason: e.to_string() })?;
+    target.freshness.check(&release, &url, Utc::now())?;
+
+    if let Some(cache) = &cache {
+        let cached = fs::read_to_string(cache).ok().and_then(|content| Release::parse(&content).ok());
+        if let (Some(Ok(Some(old))), Ok(Some(new))) = (cached.map(|cached| cached.date()), release.date()) {
+            if new < old {
+                observer.on_event(&ProgressEvent::Warning {
+                    subject: url.clone(),
+                    message: format!(
+                        "mirror Date {} is older than the cached Release ({})",
+                        format_date(new),
+                        format_date(old)
+                    ),
+                });
+                return Err(Error::InvalidRelease { url, reason: "rollback to an older Release".to_string() });
+            }
+        }
+        // 只读时不记录新的回滚基准
+        if !target.read_only_cache {
+            if let Err(e) = write_atomic(cache, &bytes) {
+                observer.on_event(&ProgressEvent::Error { subject: cache.display().to_string(), message: e.to_string() });
+            }
+        }
+    }
+
+    observer.on_event(&ProgressEvent::IndexFetchFinished { url, bytes: bytes.len() as u64 });
+    Ok(release)
+}
+
+/// 解析 `Release` 文件中的时间，例如 `Thu, 23 Apr 2020 17:33:17 UTC`
+fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    // RFC 2822 没有 `UTC` 这个时区名，但 Debian 和 Ubuntu 的仓库都这样写
+    let normalized = match value.strip_suffix(" UTC") {
+        Some(rest) => format!("{} +0000", rest),
+        None => value.to_string(),
+    };
+    DateTime::parse_from_rfc2822(&normalized)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| Error::Parse(format!("invalid date {:?}: {}", value, e)))
+}
+
+/// 以 `Release` 文件的格式输出时间
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S UTC").to_string()
+}
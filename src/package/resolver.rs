@@ -0,0 +1,433 @@
+//! # 依赖解析模块
+//!
+//! 这个模块根据包索引计算一个包的依赖闭包，即安装该包所需的全部包。
+//!
+//! 解析是一个回溯搜索：每一步找到第一个尚未满足的依赖（已经安装或已经选中的包满足
+//! 约束时视为已满足），按顺序尝试它的候选包，选中的包与其他包冲突或者之后的依赖
+//! 无法满足时撤销选择并尝试下一个候选包。候选包的顺序是：
+//!
+//! - 按 `a | b` 中书写的顺序
+//! - 同一个名称下，真实包优先于虚包的提供者
+//! - 真实包中，与声明依赖的包相同架构的包优先，其次版本从高到低
+//!
+//! 架构按 [`multiarch`](super::multiarch) 的规则匹配。`Pre-Depends` 与 `Depends` 同样处理。
+//!
+//! [`ResolveOptions`] 启用 `Recommends`（或 `Suggests`）时，所有依赖满足之后再按同样的
+//! 顺序尝试满足这些软依赖：候选包连同它的依赖都能加入时才选中，否则跳过这组候选项，
+//! 软依赖永远不会导致解析失败。
+//!
+//! 两个包不能同时选中的情况：
+//!
+//! - 同名包不能同时安装（见 [`co_installable`]）
+//! - 任何一方的 `Conflicts` 或 `Breaks` 包含另一方（包括另一方 `Provides` 的虚包）
+//!
+//! 已安装的包同样参与冲突检查。新的包同时 `Conflicts`（或 `Breaks`）和 `Replaces` 一个
+//! 已安装的包时，已安装的包会被替换，不再视为冲突；同名包的不同版本视为升级。
+//!
+//! 依赖也可以由 `Provides` 中声明了同名虚包的包满足。候选项是虚包时，按以下顺序
+//! 选择提供者，保证结果不依赖索引的顺序：
+//!
+//! 1. [`ResolveOptions::preferred_providers`] 中列出的包，按列出的顺序
+//! 2. 与声明依赖的包相同架构的包
+//! 3. 包名的字典序，同名时取最高版本
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::index::PackageIndex;
+//! use mini_apt::package::package_info::parse_packages_file;
+//! use mini_apt::package::resolver::{dependency_closure, ResolveOptions};
+//!
+//! let index = "\
+//! Package: hello
+//! Version: 2.10-2
+//! Architecture: arm64
+//! Depends: libc6 (>= 2.17)
+//! Filename: pool/main/h/hello/hello_2.10-2_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//!
+//! Package: libc6
+//! Version: 2.31-0ubuntu9
+//! Architecture: arm64
+//! Filename: pool/main/g/glibc/libc6_2.31-0ubuntu9_arm64.deb
+//! Size: 100
+//! MD5sum: 0
+//! SHA256: 0
+//! ";
+//! let packages = parse_packages_file(index);
+//! let hello = packages.find("hello", "arm64").unwrap();
+//! let options = ResolveOptions::new("arm64");
+//! let closure = dependency_closure(&packages, hello, &PackageIndex::new(), &options).unwrap();
+//! let names: Vec<_> = closure.iter().map(|p| p.package.as_str()).collect();
+//! assert_eq!(names, ["hello", "libc6"]);
+//! ```
+
+use super::index::PackageIndex;
+use super::multiarch::{co_installable, effective_architecture, satisfies_architecture};
+use super::relation::{format_alternatives, Alternatives, Dependency};
+use super::version::compare_versions;
+use super::PackageInfo;
+use crate::error::{Error, Result};
+
+/// 依赖解析选项
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// 本机（主）架构，`Architecture: all` 的包按这个架构解析依赖
+    pub native_architecture: String,
+    /// 有多个包提供同一个虚包时优先选择的包，按优先顺序排列
+    pub preferred_providers: Vec<String>,
+    /// 是否尽量满足 `Recommends`
+    pub install_recommends: bool,
+    /// 是否尽量满足 `Suggests`
+    pub install_suggests: bool,
+}
+
+impl ResolveOptions {
+    /// 创建没有首选提供者、不安装 `Recommends` 和 `Suggests` 的选项
+    pub fn new(native_architecture: &str) -> Self {
+        Self {
+            native_architecture: native_architecture.to_string(),
+            preferred_providers: Vec::new(),
+            install_recommends: false,
+            install_suggests: false,
+        }
+    }
+}
+
+/// 搜索的最大步数，超过时放弃并报告无法解析
+const MAX_STEPS: usize = 100_000;
+
+/// 计算包的依赖闭包
+///
+/// # 参数
+///
+/// * `packages` - 可供选择的包
+/// * `root` - 请求安装的包，可以不在 `packages` 中（例如本地 `.deb` 文件）
+/// * `installed` - 已安装的包，满足约束的已安装包不会出现在结果中
+/// * `options` - 解析选项
+///
+/// # 返回值
+///
+/// 按选中顺序排列的包，第一个是 `root`
+///
+/// # 错误
+///
+/// 无法找到一组满足所有依赖且互不冲突的包时返回错误，说明失败的原因：某个依赖的
+/// 所有候选项都不存在或版本不满足约束时返回 [`Error::Unsatisfied`]；唯一的候选包与
+/// 已选中或已安装的包冲突时返回 [`Error::Conflict`]；每个候选包都因为冲突或者它自己的
+/// 依赖而失败时返回 [`Error::Unresolvable`]，其中列出每个候选包失败的原因
+pub fn dependency_closure<'a>(
+    packages: &'a PackageIndex,
+    root: &'a PackageInfo,
+    installed: &PackageIndex,
+    options: &ResolveOptions,
+) -> Result<Vec<&'a PackageInfo>> {
+    let mut solver = Solver {
+        packages,
+        installed,
+        options,
+        native: options.native_architecture.as_str(),
+        selected: Vec::new(),
+        parents: Vec::new(),
+        steps: 0,
+    };
+    solver.check_compatible(root)?;
+    solver.selected.push(root);
+    solver.parents.push(None);
+    solver.solve()?;
+    solver.add_soft_dependencies();
+    Ok(solver.selected)
+}
+
+/// 回溯搜索的状态
+struct Solver<'a, 'o> {
+    packages: &'a PackageIndex,
+    installed: &'o PackageIndex,
+    options: &'o ResolveOptions,
+    native: &'o str,
+    selected: Vec<&'a PackageInfo>,
+    /// 每个选中的包是因为哪个选中的包的依赖而选中的，`root` 为 `None`
+    parents: Vec<Option<usize>>,
+    steps: usize,
+}
+
+impl<'a> Solver<'a, '_> {
+    /// 满足当前选中包的所有依赖，失败时 `selected` 恢复原状
+    fn solve(&mut self) -> Result<()> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(Error::Unresolvable {
+                package: self.selected[0].package.clone(),
+                dependency: "its dependencies".to_string(),
+                chain: Vec::new(),
+                reasons: vec![format!("gave up after {} steps", MAX_STEPS)],
+            });
+        }
+        let Some((index, alternatives)) = self.next_unsatisfied() else {
+            return Ok(());
+        };
+        let package = self.selected[index];
+        let arch = effective_architecture(&package.architecture, self.native);
+        let candidates = self.candidates(alternatives, arch);
+        if candidates.is_empty() {
+            return Err(Error::Unsatisfied {
+                package: package.package.clone(),
+                dependency: format_alternatives(alternatives),
+                chain: self.chain(index),
+            });
+        }
+
+        let mut failures = Vec::new();
+        for &candidate in &candidates {
+            if let Err(error) = self.check_compatible(candidate) {
+                failures.push((candidate, error));
+                continue;
+            }
+            self.selected.push(candidate);
+            self.parents.push(Some(index));
+            match self.solve() {
+                Ok(()) => return Ok(()),
+                Err(error) => {
+                    self.selected.pop();
+                    self.parents.pop();
+                    // 超过步数限制后其他候选包也不会再尝试，原样报告放弃的原因
+                    if self.steps > MAX_STEPS {
+                        return Err(error);
+                    }
+                    failures.push((candidate, error));
+                }
+            }
+        }
+        if failures.len() == 1 {
+            return Err(failures.pop().unwrap().1);
+        }
+        Err(Error::Unresolvable {
+            package: package.package.clone(),
+            dependency: format_alternatives(alternatives),
+            chain: self.chain(index),
+            reasons: failures
+                .into_iter()
+                .map(|(candidate, error)| format!("{} {}: {}", candidate.package, candidate.version, summary(&error)))
+                .collect(),
+        })
+    }
+
+    /// 尽量满足选中的包（包括因此新选中的包）启用的软依赖，无法满足的跳过
+    ///
+    /// 软依赖有自己的 [`MAX_STEPS`] 步数限制，不受必需依赖的搜索消耗的步数影响。
+    fn add_soft_dependencies(&mut self) {
+        let options = self.options;
+        self.steps = 0;
+        let mut index = 0;
+        while index < self.selected.len() && self.steps <= MAX_STEPS {
+            let package = self.selected[index];
+            let arch = effective_architecture(&package.architecture, self.native);
+            let recommends = package.recommends.iter().filter(|_| options.install_recommends);
+            let suggests = package.suggests.iter().filter(|_| options.install_suggests);
+            for alternatives in recommends.chain(suggests) {
+                if self.is_satisfied(alternatives, arch) {
+                    continue;
+                }
+                for candidate in self.candidates(alternatives, arch) {
+                    if self.check_compatible(candidate).is_err() {
+                        continue;
+                    }
+                    self.selected.push(candidate);
+                    self.parents.push(Some(index));
+                    if self.solve().is_ok() {
+                        break;
+                    }
+                    self.selected.pop();
+                    self.parents.pop();
+                }
+            }
+            index += 1;
+        }
+    }
+
+    /// 从请求的包到第 `index` 个选中的包的依赖链
+    fn chain(&self, index: usize) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut next = Some(index);
+        while let Some(i) = next {
+            chain.push(self.selected[i].package.clone());
+            next = self.parents[i];
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// 选中的包中第一个尚未满足的依赖，返回声明依赖的包在 `selected` 中的位置
+    fn next_unsatisfied(&self) -> Option<(usize, &'a Alternatives)> {
+        self.selected.iter().enumerate().find_map(|(index, &package)| {
+            let arch = effective_architecture(&package.architecture, self.native);
+            package
+                .pre_depends
+                .iter()
+                .chain(&package.depends)
+                .find(|alternatives| !self.is_satisfied(alternatives, arch))
+                .map(|alternatives| (index, alternatives))
+        })
+    }
+
+    /// 已安装（且不会被替换）或已选中的包能否满足一组候选项
+    fn is_satisfied(&self, alternatives: &Alternatives, arch: &str) -> bool {
+        alternatives.iter().any(|dep| {
+            let installed = self
+                .installed
+                .get(&dep.name)
+                .chain(self.installed.providers(&dep.name))
+                .filter(|p| !self.is_replaced(p));
+            installed
+                .chain(self.selected.iter().copied())
+                .any(|p| satisfies(dep, arch, p, self.native))
+        })
+    }
+
+    /// 一组候选项的所有候选包，按尝试顺序排列，不重复
+    fn candidates(&self, alternatives: &Alternatives, arch: &str) -> Vec<&'a PackageInfo> {
+        let mut candidates: Vec<&'a PackageInfo> = Vec::new();
+        for dep in alternatives {
+            let mut real: Vec<&'a PackageInfo> = self
+                .packages
+                .get(&dep.name)
+                .filter(|p| satisfies(dep, arch, p, self.native))
+                .collect();
+            real.sort_by(|a, b| {
+                let same_arch = |p: &PackageInfo| effective_architecture(&p.architecture, self.native) == arch;
+                same_arch(b)
+                    .cmp(&same_arch(a))
+                    .then_with(|| compare_versions(&b.version, &a.version))
+            });
+            let provided = providers(self.packages, dep, arch, self.options);
+            for candidate in real.into_iter().chain(provided) {
+                if !candidates.iter().any(|c| std::ptr::eq(*c, candidate)) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+        candidates
+    }
+
+    /// 候选包能否与已选中和已安装的包共存
+    fn check_compatible(&self, candidate: &PackageInfo) -> Result<()> {
+        let conflict = |other: &PackageInfo, reason: String| Error::Conflict {
+            package: format!("{}:{}", candidate.package, candidate.architecture),
+            other: format!("{}:{}", other.package, other.architecture),
+            reason,
+        };
+        for &other in &self.selected {
+            if !co_installable(other, candidate, self.native) {
+                let reason = if other.architecture == candidate.architecture {
+                    format!("version {} is already selected", other.version)
+                } else {
+                    "only packages marked Multi-Arch: same can be installed for several architectures".to_string()
+                };
+                return Err(conflict(other, reason));
+            }
+            if let Some(reason) = conflict_reason(candidate, other).or_else(|| conflict_reason(other, candidate)) {
+                return Err(conflict(other, reason));
+            }
+        }
+        for other in self.installed.iter() {
+            if self.is_replaced(other) || replaces(candidate, other, self.native) {
+                continue;
+            }
+            if let Some(reason) = conflict_reason(candidate, other).or_else(|| conflict_reason(other, candidate)) {
+                return Err(conflict(other, format!("{}, which is installed", reason)));
+            }
+        }
+        Ok(())
+    }
+
+    /// 已安装的包是否会被某个选中的包升级或替换
+    fn is_replaced(&self, installed: &PackageInfo) -> bool {
+        self.selected.iter().any(|p| replaces(p, installed, self.native))
+    }
+}
+
+/// 嵌套在其他失败原因中的错误说明，[`Error::Unresolvable`] 只保留失败的依赖，
+/// 避免说明的长度随搜索的分支数增长
+fn summary(error: &Error) -> String {
+    match error {
+        Error::Unresolvable { package, dependency, .. } => {
+            format!("{} depends on {}, which cannot be satisfied", package, dependency)
+        }
+        other => other.to_string(),
+    }
+}
+
+/// `package` 安装后 `installed` 是否不再存在：同名同架构（升级），或者 `package`
+/// 同时 `Conflicts`（或 `Breaks`）和 `Replaces` 它
+fn replaces(package: &PackageInfo, installed: &PackageInfo, native: &str) -> bool {
+    if package.package == installed.package {
+        return effective_architecture(&package.architecture, native)
+            == effective_architecture(&installed.architecture, native);
+    }
+    conflict_reason(package, installed).is_some() && package.replaces.iter().any(|dep| installed.matches_relation(dep))
+}
+
+/// `package` 的 `Conflicts` 或 `Breaks` 是否包含 `other`，包含时返回说明
+///
+/// 包不会与自己冲突，因此 `Provides: x, Conflicts: x` 只排斥其他提供 `x` 的包。
+fn conflict_reason(package: &PackageInfo, other: &PackageInfo) -> Option<String> {
+    if package.package == other.package {
+        return None;
+    }
+    let fields = [("Conflicts", &package.conflicts), ("Breaks", &package.breaks)];
+    fields.into_iter().find_map(|(field, relations)| {
+        relations
+            .iter()
+            .find(|dep| other.matches_relation(dep))
+            .map(|dep| format!("{} {}: {}", package.package, field, dep))
+    })
+}
+
+/// 通过 `Provides` 满足依赖的包，按选择顺序排列
+///
+/// 顺序见[模块文档](self)。同一个包名只保留最好的一个版本。
+///
+/// # 参数
+///
+/// * `packages` - 可供选择的包
+/// * `dependency` - 依赖，`name` 是虚包名
+/// * `dependent_architecture` - 声明依赖的包的架构
+/// * `options` - 解析选项
+pub fn providers<'a>(
+    packages: &'a PackageIndex,
+    dependency: &Dependency,
+    dependent_architecture: &str,
+    options: &ResolveOptions,
+) -> Vec<&'a PackageInfo> {
+    let native = options.native_architecture.as_str();
+    let rank = |p: &PackageInfo| {
+        let preferred = options
+            .preferred_providers
+            .iter()
+            .position(|name| *name == p.package)
+            .unwrap_or(usize::MAX);
+        let other_arch = effective_architecture(&p.architecture, native) != dependent_architecture;
+        (preferred, other_arch)
+    };
+    let mut providers: Vec<&PackageInfo> = packages
+        .providers(&dependency.name)
+        .filter(|p| satisfies(dependency, dependent_architecture, p, native))
+        .collect();
+    providers.sort_by(|a, b| {
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.package.cmp(&b.package))
+            .then_with(|| compare_versions(&b.version, &a.version))
+    });
+    providers.dedup_by(|a, b| a.package == b.package);
+    providers
+}
+
+/// 包能否满足依赖（架构和版本），包括通过 `Provides` 满足
+pub(crate) fn satisfies(dependency: &Dependency, dependent_architecture: &str, candidate: &PackageInfo, native: &str) -> bool {
+    let by_name = candidate.package == dependency.name && dependency.matches_version(&candidate.version);
+    let by_provides = || candidate.provides.iter().any(|provided| dependency.is_provided_by(provided));
+    (by_name || by_provides()) && satisfies_architecture(dependency, dependent_architecture, candidate, native)
+}
@@ -0,0 +1,407 @@
+//! # 安装状态数据库
+//!
+//! 这个模块以 dpkg 的格式记录安装根目录中已安装的包：
+//!
+//! - `var/lib/dpkg/status`: 每个已安装包的控制字段，加上 `Status: install ok installed`
+//! - `var/lib/dpkg/info/<包名>.list`: 包安装的文件列表，每行一个以 `/` 开头的路径；
+//!   `Multi-Arch: same` 的包为 `<包名>:<架构>.list`
+//!
+//! 因此 sysroot 可以直接交给 `dpkg --root` 或 `dpkg-query --admindir` 查询。
+//! 维护脚本不会被执行。
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::package::status::StatusDb;
+//! use std::path::Path;
+//!
+//! let db = StatusDb::load(Path::new("sysroot")).unwrap();
+//! for package in db.installed() {
+//!     println!("{} {}", package.get("Package").unwrap_or(""), package.get("Version").unwrap_or(""));
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::control::{parse_stanzas, Stanza};
+use super::index::PackageIndex;
+use super::multiarch::split_qualified_name;
+use super::package_info::read_control_fields;
+use super::PackageInfo;
+use crate::error::{Error, Result};
+
+/// 状态文件，相对于安装根目录
+pub const STATUS_FILE: &str = "var/lib/dpkg/status";
+
+/// 文件列表目录，相对于安装根目录
+pub const INFO_DIR: &str = "var/lib/dpkg/info";
+
+/// 已安装包的状态值
+const INSTALLED: &str = "install ok installed";
+
+/// 串行化对状态文件的读-改-写，避免同时进行的更新互相覆盖记录
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 安装状态数据库
+#[derive(Debug, Clone)]
+pub struct StatusDb {
+    root_dir: PathBuf,
+    entries: Vec<Stanza>,
+}
+
+impl StatusDb {
+    /// 读取安装根目录中的状态数据库，状态文件不存在时返回空数据库
+    pub fn load(root_dir: &Path) -> Result<Self> {
+        let path = root_dir.join(STATUS_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => parse_stanzas(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::io_at(path, e)),
+        };
+        Ok(Self {
+            root_dir: root_dir.to_path_buf(),
+            entries,
+        })
+    }
+
+    /// 在锁内读取、修改并保存状态数据库
+    ///
+    /// 每个包解包后通过这个方法记录结果。
+    pub fn update<T>(root_dir: &Path, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _guard = UPDATE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut db = Self::load(root_dir)?;
+        let result = f(&mut db)?;
+        db.save()?;
+        Ok(result)
+    }
+
+    /// 所有已安装的包
+    pub fn installed(&self) -> impl Iterator<Item = &Stanza> {
+        self.entries.iter().filter(|entry| entry.get("Status") == Some(INSTALLED))
+    }
+
+    /// 查找已安装的包，`name` 可以写作 `包名:架构`
+    pub fn get(&self, name: &str) -> Option<&Stanza> {
+        let (name, arch) = split_qualified_name(name);
+        self.installed().find(|entry| {
+            entry.get("Package") == Some(name) && arch.is_none_or(|arch| entry.get("Architecture") == Some(arch))
+        })
+    }
+
+    /// 已安装包的版本
+    pub fn installed_version(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|entry| entry.get("Version"))
+    }
+
+    /// 所有已安装的包，用于依赖解析
+    ///
+    /// 包信息中没有下载相关的字段（文件名、大小和校验和）。
+    pub fn installed_packages(&self) -> PackageIndex {
+        self.installed().filter_map(package_info).collect()
+    }
+
+    /// 读取包安装的文件列表，`name` 可以写作 `包名:架构`
+    pub fn files(&self, name: &str) -> Result<Vec<PathBuf>> {
+        let entry = self
+            .get(name)
+            .ok_or_else(|| Error::Parse(format!("package {} is not installed", name)))?;
+        self.read_list(entry)
+    }
+
+    /// 检查安装一个包是否会覆盖其他已安装包的文件
+    ///
+    /// 与 dpkg 一样，只有新包 `Replaces` 文件的所有者时才允许覆盖；同名的包（升级或
+    /// `Multi-Arch: same` 的其他架构）共有的文件不算冲突。应在解包之前调用。
+    ///
+    /// # 参数
+    ///
+    /// * `control` - 新包的控制字段
+    /// * `files` - 新包中目录以外的条目，相对于安装根目录
+    ///
+    /// # 错误
+    ///
+    /// 有文件属于另一个没有被 `Replaces` 的包时返回 [`Error::Conflict`]
+    pub fn check_overwrite(&self, control: &Stanza, files: &[PathBuf]) -> Result<()> {
+        let name = control.get("Package").unwrap_or_default();
+        let mut package = PackageInfo::new(
+            name.to_string(),
+            control.get("Version").unwrap_or_default().to_string(),
+            control.get("Architecture").unwrap_or_default().to_string(),
+            String::new(),
+            0,
+            String::new(),
+            String::new(),
+        );
+        read_control_fields(&mut package, control)?;
+        let new_files: HashSet<PathBuf> = files.iter().map(|file| Path::new("/").join(file)).collect();
+        for entry in self.installed().filter(|entry| entry.get("Package") != Some(name)) {
+            let Some(other) = package_info(entry) else { continue };
+            if package.replaces.iter().any(|dep| other.matches_relation(dep)) {
+                continue;
+            }
+            if let Some(file) = self.read_list_or_empty(entry)?.into_iter().find(|file| new_files.contains(file)) {
+                return Err(Error::Conflict {
+                    package: format!("{}:{}", package.package, package.architecture),
+                    other: format!("{}:{}", other.package, other.architecture),
+                    reason: format!("trying to overwrite {}, which is also in package {}", file.display(), other.package),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录一个已安装的包
+    ///
+    /// 升级已安装的包时，旧版本有而新版本没有、也不属于其他包的文件被删除。
+    /// 与 dpkg 一样处理 `Replaces`：被替换的包中与新包相同的文件归新包所有；新包同时
+    /// `Conflicts`（或 `Breaks`）被替换的包时，被替换的包整个移除，只属于它的文件被删除。
+    ///
+    /// # 参数
+    ///
+    /// * `control` - 包的控制字段
+    /// * `files` - 包安装的文件，相对于安装根目录
+    pub fn record(&mut self, control: &Stanza, files: &[PathBuf]) -> Result<()> {
+        let name = control
+            .get("Package")
+            .ok_or_else(|| Error::Parse("control file has no Package field".to_string()))?
+            .to_string();
+
+        let mut entry = Stanza::default();
+        entry.set("Package", name.as_str());
+        entry.set("Status", INSTALLED);
+        for (key, value) in control.fields() {
+            if !key.eq_ignore_ascii_case("Package") && !key.eq_ignore_ascii_case("Status") {
+                entry.set(key, value);
+            }
+        }
+        // 只有 Multi-Arch: same 的包可以同时安装多个架构，其他情况替换所有同名包
+        let same = |stanza: &Stanza| stanza.get("Multi-Arch") == Some("same");
+        let (previous, kept): (Vec<Stanza>, Vec<Stanza>) = self.entries.drain(..).partition(|existing| {
+            existing.get("Package") == Some(name.as_str())
+                && !(same(existing) && same(&entry) && existing.get("Architecture") != entry.get("Architecture"))
+        });
+        self.entries = kept;
+        let mut old_files = Vec::new();
+        for existing in &previous {
+            old_files.extend(self.read_list_or_empty(existing)?);
+        }
+        if let Some(package) = package_info(&entry) {
+            self.apply_replaces(&package, files)?;
+        }
+        let list_path = self.list_path(&entry);
+        let mut keep = self.owned_files(self.installed())?;
+        keep.extend(files.iter().map(|file| Path::new("/").join(file)));
+        self.remove_files(old_files.iter().filter(|file| !keep.contains(*file)))?;
+        // 架构变化时（例如 arm64 换成 all）旧的文件列表与新的不是同一个文件
+        for existing in &previous {
+            let old_list = self.list_path(existing);
+            if old_list != list_path {
+                remove_list(&old_list)?;
+            }
+        }
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| {
+            (a.get("Package"), a.get("Architecture")).cmp(&(b.get("Package"), b.get("Architecture")))
+        });
+
+        let mut list = String::from("/.\n");
+        for file in files {
+            list.push('/');
+            list.push_str(&file.to_string_lossy());
+            list.push('\n');
+        }
+        write_atomic(&list_path, list.as_bytes())
+    }
+
+    /// 删除一个已安装的包，`name` 可以写作 `包名:架构`
+    ///
+    /// 删除只属于这个包的文件和因此变空的目录，以及文件列表和状态条目。
+    /// 与 [`StatusDb::record`] 一样不执行维护脚本。
+    ///
+    /// # 返回值
+    ///
+    /// 删除的文件数
+    ///
+    /// # 错误
+    ///
+    /// 包没有安装，或者删除文件失败时返回错误
+    pub fn remove(&mut self, name: &str) -> Result<usize> {
+        let entry = self
+            .get(name)
+            .ok_or_else(|| Error::Parse(format!("package {} is not installed", name)))?
+            .clone();
+        let files = self.read_list_or_empty(&entry)?;
+        let shared = self.owned_files(self.installed().filter(|other| **other != entry))?;
+        let removed = self.remove_files(files.iter().filter(|file| !shared.contains(*file)))?;
+        remove_list(&self.list_path(&entry))?;
+        self.entries.retain(|existing| *existing != entry);
+        Ok(removed)
+    }
+
+    /// 保存状态文件
+    pub fn save(&self) -> Result<()> {
+        let content: String = self.entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        write_atomic(&self.root_dir.join(STATUS_FILE), content.as_bytes())
+    }
+
+    /// 处理新安装的包的 `Replaces`
+    fn apply_replaces(&mut self, package: &PackageInfo, files: &[PathBuf]) -> Result<()> {
+        let new_files: HashSet<PathBuf> = files.iter().map(|file| Path::new("/").join(file)).collect();
+        let mut removed = Vec::new();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if entry.get("Status") != Some(INSTALLED) || entry.get("Package") == Some(package.package.as_str()) {
+                continue;
+            }
+            let Some(other) = package_info(entry) else { continue };
+            if !package.replaces.iter().any(|dep| other.matches_relation(dep)) {
+                continue;
+            }
+            let conflicts = package.conflicts.iter().chain(&package.breaks).any(|dep| other.matches_relation(dep));
+            if conflicts {
+                removed.push(index);
+            } else {
+                // 目录仍由两个包共有，只移交文件
+                let kept = |file: &&PathBuf| !new_files.contains(*file) || self.is_directory(file);
+                let mut list = String::from("/.\n");
+                for file in self.read_list_or_empty(entry)?.iter().filter(kept) {
+                    list.push_str(&file.to_string_lossy());
+                    list.push('\n');
+                }
+                write_atomic(&self.list_path(entry), list.as_bytes())?;
+            }
+        }
+        if removed.is_empty() {
+            return Ok(());
+        }
+
+        // 移除的包之间、以及与留下的包共有的文件只在没有包再拥有时删除
+        let remaining = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(index, entry)| !removed.contains(index) && entry.get("Status") == Some(INSTALLED))
+            .map(|(_, entry)| entry);
+        let mut keep = self.owned_files(remaining)?;
+        keep.extend(new_files);
+        for &index in &removed {
+            let entry = &self.entries[index];
+            let old_files = self.read_list_or_empty(entry)?;
+            self.remove_files(old_files.iter().filter(|file| !keep.contains(*file)))?;
+            remove_list(&self.list_path(entry))?;
+        }
+        for index in removed.into_iter().rev() {
+            self.entries.remove(index);
+        }
+        Ok(())
+    }
+
+    /// 删除安装根目录中的文件和因此变空的目录
+    ///
+    /// `files` 是文件列表中以 `/` 开头的路径，已经不存在的文件被忽略。返回删除的文件数。
+    fn remove_files<'a>(&self, files: impl Iterator<Item = &'a PathBuf>) -> Result<usize> {
+        let mut removed = 0;
+        let mut directories = Vec::new();
+        for file in files {
+            let path = self.root_dir.join(file.strip_prefix("/").unwrap_or(file));
+            match fs::symlink_metadata(&path) {
+                Ok(meta) if meta.is_dir() => directories.push(path),
+                Ok(_) => {
+                    fs::remove_file(&path).map_err(|e| Error::io_at(&path, e))?;
+                    removed += 1;
+                }
+                Err(_) => {}
+            }
+        }
+        // 子目录排在父目录之后，倒序删除；仍有其他文件的目录保留
+        directories.sort();
+        for directory in directories.iter().rev() {
+            let _ = fs::remove_dir(directory);
+        }
+        Ok(removed)
+    }
+
+    /// 文件列表中的路径在安装根目录中是否为目录
+    fn is_directory(&self, file: &Path) -> bool {
+        let path = self.root_dir.join(file.strip_prefix("/").unwrap_or(file));
+        fs::symlink_metadata(path).is_ok_and(|meta| meta.is_dir())
+    }
+
+    /// 一组状态条目拥有的所有文件
+    fn owned_files<'a>(&self, entries: impl Iterator<Item = &'a Stanza>) -> Result<HashSet<PathBuf>> {
+        let mut files = HashSet::new();
+        for entry in entries {
+            files.extend(self.read_list_or_empty(entry)?);
+        }
+        Ok(files)
+    }
+
+    /// 读取一个状态条目的文件列表
+    fn read_list(&self, entry: &Stanza) -> Result<Vec<PathBuf>> {
+        let path = self.list_path(entry);
+        let content = fs::read_to_string(&path).map_err(|e| Error::io_at(&path, e))?;
+        Ok(content
+            .lines()
+            .filter(|line| !line.is_empty() && *line != "/.")
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// 读取文件列表，列表文件不存在时视为没有文件
+    fn read_list_or_empty(&self, entry: &Stanza) -> Result<Vec<PathBuf>> {
+        match self.read_list(entry) {
+            Err(Error::IoAt { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            result => result,
+        }
+    }
+
+    /// 文件列表的路径，与 dpkg 一样只有 `Multi-Arch: same` 的包带架构后缀
+    fn list_path(&self, entry: &Stanza) -> PathBuf {
+        let name = entry.get("Package").unwrap_or_default();
+        let file = match (entry.get("Multi-Arch"), entry.get("Architecture")) {
+            (Some("same"), Some(arch)) => format!("{}:{}.list", name, arch),
+            _ => format!("{}.list", name),
+        };
+        self.root_dir.join(INFO_DIR).join(file)
+    }
+}
+
+/// 删除文件列表，已经不存在时忽略
+fn remove_list(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(Error::io_at(path, e)),
+        _ => Ok(()),
+    }
+}
+
+/// 把状态条目转换为包信息，没有下载相关的字段
+fn package_info(entry: &Stanza) -> Option<PackageInfo> {
+    let mut package = PackageInfo::new(
+        entry.get("Package")?.to_string(),
+        entry.get("Version")?.to_string(),
+        entry.get("Architecture")?.to_string(),
+        String::new(),
+        0,
+        String::new(),
+        String::new(),
+    );
+    read_control_fields(&mut package, entry).ok()?;
+    Some(package)
+}
+
+/// 先写入临时文件再改名，避免中断时留下不完整的文件
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| Error::io_at(parent, e))?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".new");
+    let temp = PathBuf::from(temp);
+    let mut file = fs::File::create(&temp).map_err(|e| Error::io_at(&temp, e))?;
+    file.write_all(content).map_err(|e| Error::io_at(&temp, e))?;
+    file.sync_all().map_err(|e| Error::io_at(&temp, e))?;
+    fs::rename(&temp, path).map_err(|e| Error::io_at(path, e))
+}
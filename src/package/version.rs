@@ -0,0 +1,196 @@
+//! # 版本号模块
+//!
+//! 这个模块实现 Debian 版本号的比较规则（与 `dpkg --compare-versions` 一致）。
+//!
+//! 版本号的格式为 `[epoch:]upstream_version[-debian_revision]`：
+//!
+//! - epoch 按数值比较，省略时为 0
+//! - 其余部分交替比较非数字段和数字段：非数字段逐字符比较，字母排在非字母之前，
+//!   `~` 排在所有字符（包括空字符串）之前；数字段按数值比较
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::version::{compare_versions, Relation, VersionConstraint};
+//! use std::cmp::Ordering;
+//!
+//! assert_eq!(compare_versions("1.0~rc1", "1.0"), Ordering::Less);
+//! assert_eq!(compare_versions("1:0.9", "2.0"), Ordering::Greater);
+//! assert_eq!(compare_versions("2.31-0ubuntu9.9", "2.31-0ubuntu9.10"), Ordering::Less);
+//!
+//! let constraint = VersionConstraint::new(Relation::LaterEqual, "2.17");
+//! assert!(constraint.matches("2.31-0ubuntu9"));
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// 比较两个 Debian 版本号
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (epoch_a, upstream_a, revision_a) = split_version(a);
+    let (epoch_b, upstream_b, revision_b) = split_version(b);
+    epoch_a
+        .cmp(&epoch_b)
+        .then_with(|| compare_fragment(upstream_a, upstream_b))
+        .then_with(|| compare_fragment(revision_a, revision_b))
+}
+
+/// 把版本号拆分为 (epoch, upstream_version, debian_revision)
+fn split_version(version: &str) -> (u64, &str, &str) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) if !epoch.is_empty() && epoch.bytes().all(|b| b.is_ascii_digit()) => {
+            (epoch.parse().unwrap_or(u64::MAX), rest)
+        }
+        _ => (0, version),
+    };
+    match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (epoch, upstream, revision),
+        None => (epoch, rest, ""),
+    }
+}
+
+/// 非数字段中单个字符的排序权重，`None` 表示字符串结束
+fn char_order(c: Option<u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(b'~') => -1,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+/// dpkg 的 `verrevcmp` 算法
+fn compare_fragment(a: &str, b: &str) -> Ordering {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let ac = char_order(a.get(i).copied());
+            let bc = char_order(b.get(j).copied());
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+        while i < a.len() && a[i] == b'0' {
+            i += 1;
+        }
+        while j < b.len() && b[j] == b'0' {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while i < a.len() && a[i].is_ascii_digit() && j < b.len() && b[j].is_ascii_digit() {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if i < a.len() && a[i].is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if j < b.len() && b[j].is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+/// 版本关系运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Relation {
+    /// `<<`
+    Earlier,
+    /// `<=`
+    EarlierEqual,
+    /// `=`
+    Equal,
+    /// `>=`
+    LaterEqual,
+    /// `>>`
+    Later,
+}
+
+impl Relation {
+    /// 比较结果是否满足该关系
+    pub fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            Relation::Earlier => ordering == Ordering::Less,
+            Relation::EarlierEqual => ordering != Ordering::Greater,
+            Relation::Equal => ordering == Ordering::Equal,
+            Relation::LaterEqual => ordering != Ordering::Less,
+            Relation::Later => ordering == Ordering::Greater,
+        }
+    }
+
+    /// 运算符的标准写法
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relation::Earlier => "<<",
+            Relation::EarlierEqual => "<=",
+            Relation::Equal => "=",
+            Relation::LaterEqual => ">=",
+            Relation::Later => ">>",
+        }
+    }
+}
+
+impl FromStr for Relation {
+    type Err = Error;
+
+    /// 解析运算符，过时的 `<` 和 `>` 分别视为 `<=` 和 `>=`
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "<<" => Ok(Relation::Earlier),
+            "<=" | "<" => Ok(Relation::EarlierEqual),
+            "=" => Ok(Relation::Equal),
+            ">=" | ">" => Ok(Relation::LaterEqual),
+            ">>" => Ok(Relation::Later),
+            other => Err(Error::Parse(format!("invalid version relation: {}", other))),
+        }
+    }
+}
+
+impl fmt::Display for Relation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 版本约束，例如 `>= 2.17`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionConstraint {
+    /// 关系运算符
+    pub relation: Relation,
+    /// 比较的版本号
+    pub version: String,
+}
+
+impl VersionConstraint {
+    /// 创建版本约束
+    pub fn new(relation: Relation, version: impl Into<String>) -> Self {
+        Self {
+            relation,
+            version: version.into(),
+        }
+    }
+
+    /// 版本号是否满足约束
+    pub fn matches(&self, version: &str) -> bool {
+        self.relation.accepts(compare_versions(version, &self.version))
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.relation, self.version)
+    }
+}
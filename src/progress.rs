@@ -0,0 +1,245 @@
+//! # 进度报告模块
+//!
+//! 这个模块定义了下载和安装过程中的进度事件，以及接收这些事件的观察者接口。
+//!
+//! 库本身从不直接向标准输出打印内容，所有进度信息都通过 [`ProgressObserver`]
+//! 传递给调用方：
+//!
+//! - [`SilentObserver`] — 忽略所有事件，是库的默认行为
+//! - [`TerminalProgress`] — 在终端（标准错误）上绘制进度条，供命令行工具使用
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::progress::{ProgressEvent, ProgressObserver};
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//!
+//! /// 只统计接收字节数的观察者
+//! #[derive(Default)]
+//! struct ByteCounter(AtomicU64);
+//!
+//! impl ProgressObserver for ByteCounter {
+//!     fn on_event(&self, event: &ProgressEvent) {
+//!         if let ProgressEvent::BytesReceived { chunk, .. } = event {
+//!             self.0.fetch_add(*chunk, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! let counter = ByteCounter::default();
+//! counter.on_event(&ProgressEvent::BytesReceived {
+//!     url: "https://example.com/a.deb".to_string(),
+//!     chunk: 512,
+//!     received: 512,
+//!     total: Some(1024),
+//! });
+//! assert_eq!(counter.0.load(Ordering::Relaxed), 512);
+//! ```
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 进度事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// 开始下载包索引
+    IndexFetchStarted {
+        /// 索引 URL
+        url: String,
+    },
+    /// 包索引下载并解压完成
+    IndexFetchFinished {
+        /// 索引 URL
+        url: String,
+        /// 解压后的字节数
+        bytes: u64,
+    },
+    /// 开始下载文件
+    DownloadStarted {
+        /// 文件 URL
+        url: String,
+        /// 服务器报告的文件大小
+        total: Option<u64>,
+    },
+    /// 收到一段文件数据
+    BytesReceived {
+        /// 文件 URL
+        url: String,
+        /// 本次收到的字节数
+        chunk: u64,
+        /// 已收到的总字节数
+        received: u64,
+        /// 服务器报告的文件大小
+        total: Option<u64>,
+    },
+    /// 文件下载完成
+    DownloadFinished {
+        /// 文件 URL
+        url: String,
+        /// 传输的字节数
+        bytes: u64,
+    },
+    /// 文件校验通过
+    Verified {
+        /// 文件名或 URL
+        file: String,
+        /// 校验算法名称
+        algorithm: &'static str,
+    },
+    /// 开始解包
+    ExtractStarted {
+        /// 归档文件路径
+        archive: PathBuf,
+        /// 解包目标目录
+        destination: PathBuf,
+    },
+    /// 解包完成
+    ExtractFinished {
+        /// 归档文件路径
+        archive: PathBuf,
+        /// 解出的条目数
+        entries: u64,
+    },
+    /// 需要用户注意但不影响继续执行的情况
+    Warning {
+        /// 相关的对象（URL、文件等）
+        subject: String,
+        /// 警告内容
+        message: String,
+    },
+    /// 发生错误
+    ///
+    /// 错误不一定是致命的，例如某个仓库组件或镜像源不可用。
+    Error {
+        /// 出错的对象（URL、文件等）
+        subject: String,
+        /// 错误描述
+        message: String,
+    },
+}
+
+/// 进度观察者
+///
+/// 实现需要是线程安全的，因为并行下载会从多个任务中同时报告事件。
+pub trait ProgressObserver: Send + Sync {
+    /// 处理一个进度事件
+    fn on_event(&self, event: &ProgressEvent);
+}
+
+/// 忽略所有事件的观察者
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SilentObserver;
+
+impl ProgressObserver for SilentObserver {
+    fn on_event(&self, _event: &ProgressEvent) {}
+}
+
+/// 终端进度条
+///
+/// 在标准错误上为正在进行的下载绘制一行进度条，其他事件逐行输出。
+#[derive(Debug, Default)]
+pub struct TerminalProgress {
+    /// 正在进行的下载：URL -> (已收到字节数, 总字节数)
+    ///
+    /// 只绘制最近更新的下载，其余下载以数量的形式附加在进度条末尾。
+    active: Mutex<HashMap<String, (u64, Option<u64>)>>,
+}
+
+impl TerminalProgress {
+    /// 创建终端进度条
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn file_name(url: &str) -> &str {
+        url.rsplit('/').next().unwrap_or(url)
+    }
+
+    fn render_bar(url: &str, received: u64, total: Option<u64>) -> String {
+        const WIDTH: u64 = 30;
+        match total {
+            Some(total) if total > 0 => {
+                let filled = (received.min(total) * WIDTH / total) as usize;
+                format!(
+                    "[{}{}] {:>3}% {} {}/{}",
+                    "=".repeat(filled),
+                    " ".repeat(WIDTH as usize - filled),
+                    received.min(total) * 100 / total,
+                    Self::file_name(url),
+                    format_bytes(received),
+                    format_bytes(total),
+                )
+            }
+            _ => format!("{} {}", Self::file_name(url), format_bytes(received)),
+        }
+    }
+
+    /// 输出一行完整消息，覆盖当前的进度条
+    fn line(&self, message: &str) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = writeln!(stderr, "\r\x1b[K{}", message);
+    }
+}
+
+impl ProgressObserver for TerminalProgress {
+    fn on_event(&self, event: &ProgressEvent) {
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        match event {
+            ProgressEvent::IndexFetchStarted { url } => self.line(&format!("Get: {}", url)),
+            ProgressEvent::IndexFetchFinished { url, bytes } => {
+                self.line(&format!("Hit: {} ({})", url, format_bytes(*bytes)))
+            }
+            ProgressEvent::DownloadStarted { url, total } => {
+                active.insert(url.clone(), (0, *total));
+            }
+            ProgressEvent::BytesReceived { url, received, total, .. } => {
+                active.insert(url.clone(), (*received, *total));
+                let mut bar = Self::render_bar(url, *received, *total);
+                if active.len() > 1 {
+                    bar.push_str(&format!(" (+{} more)", active.len() - 1));
+                }
+                let mut stderr = std::io::stderr().lock();
+                let _ = write!(stderr, "\r\x1b[K{}", bar);
+                let _ = stderr.flush();
+            }
+            ProgressEvent::DownloadFinished { url, bytes } => {
+                active.remove(url);
+                self.line(&format!("Fetched {} ({})", Self::file_name(url), format_bytes(*bytes)));
+            }
+            ProgressEvent::Verified { file, algorithm } => {
+                self.line(&format!("{} checksum verified: {}", algorithm, Self::file_name(file)))
+            }
+            ProgressEvent::ExtractStarted { archive, destination } => self.line(&format!(
+                "Extracting {} into {}",
+                archive.display(),
+                destination.display()
+            )),
+            ProgressEvent::ExtractFinished { archive, entries } => {
+                self.line(&format!("Extracted {} entries from {}", entries, archive.display()))
+            }
+            ProgressEvent::Warning { subject, message } => self.line(&format!("W: {}: {}", subject, message)),
+            ProgressEvent::Error { subject, message } => {
+                active.remove(subject);
+                self.line(&format!("Err: {}: {}", subject, message))
+            }
+        }
+    }
+}
+
+/// 以人类可读的单位格式化字节数
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -0,0 +1,283 @@
+//! # Debian 仓库来源
+//!
+//! 从配置的镜像源下载包索引，查找请求的包，并从镜像源下载对应的 `.deb` 文件。
+//! 提供索引的镜像源优先，其余镜像源按配置顺序作为后备。
+//!
+//! 配置了 [`resolve_dependencies`](crate::config::InstallConfig::resolve_dependencies)
+//! 时，同时解析依赖闭包中的所有包。`.deb` 文件以 `name_version_arch.deb` 的标准文件名保存。
+//!
+//! 安装时 `.deb` 文件下载到 [`ARCHIVES_DIR`]，解包到安装根目录并记录到
+//! [`StatusDb`]；已安装相同版本的包会被跳过。
+
+use std::path::Path;
+
+use futures::future::BoxFuture;
+
+use super::{Artifact, ArtifactLocation, PackageSource, Resolution, SourceContext};
+use crate::config::InstallConfig;
+use crate::error::{Error, Result};
+use crate::installer::{InstallError, ResolvedPackage, SkippedItem};
+use crate::package::architecture::Architecture;
+use crate::package::checksum::Checksum;
+use crate::package::deb::DebArchive;
+use crate::package::extended_states::Mark;
+use crate::package::index::PackageIndex;
+use crate::package::multiarch::{effective_architecture, split_qualified_name};
+use crate::package::package_info::{fetch_packages_file, find_package, parse_packages_file, IndexTarget, LISTS_DIR};
+use crate::package::relation::Dependency;
+use crate::package::resolver::{dependency_closure, providers};
+use crate::package::status::StatusDb;
+use crate::package::PackageInfo;
+use crate::progress::ProgressEvent;
+use crate::utils::text::closest_match;
+
+/// 安装时 `.deb` 文件的下载目录，相对于安装根目录
+pub const ARCHIVES_DIR: &str = "var/cache/apt/archives";
+
+/// Debian/Ubuntu 仓库
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DebianSource;
+
+impl PackageSource for DebianSource {
+    fn name(&self) -> &str {
+        "debian"
+    }
+
+    fn handles(&self, _request: &str) -> bool {
+        true
+    }
+
+    fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>> {
+        Box::pin(async move {
+            let config = ctx.config;
+            if config.mirrors.is_empty() {
+                return Err(Error::Config("no mirrors configured".to_string()));
+            }
+            let (name, arch) = requested_architecture(config, request)?;
+            let mut resolution = Resolution::default();
+            let installed = installed_packages(ctx)?;
+            let mut found_index = false;
+            let mut suggestion = None;
+            for mirror in &config.mirrors {
+                let packages = match fetch_index(ctx, mirror).await {
+                    Ok(packages) => packages,
+                    Err(error) => {
+                        resolution.errors.push(InstallError { mirror: Some(mirror.clone()), error });
+                        continue;
+                    }
+                };
+                found_index = true;
+                let Some(info) = find_requested(config, &packages, name, arch)? else {
+                    suggestion = suggestion.or_else(|| suggest(&packages, name));
+                    continue;
+                };
+                add_packages(ctx, mirror, &packages, info, true, &installed, &mut resolution)?;
+                return Ok(resolution);
+            }
+
+            if found_index {
+                Err(Error::PackageNotFound {
+                    name: name.to_string(),
+                    architecture: arch.to_string(),
+                    suggestion,
+                })
+            } else {
+                Err(Error::NoMirror("package index".to_string()))
+            }
+        })
+    }
+
+    fn unpack<'a>(&'a self, ctx: &'a SourceContext<'a>, _artifact: &'a Artifact, path: &'a Path) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(install_deb(ctx, path))
+    }
+}
+
+/// 拆分请求中的包名和架构，没有指定架构时使用主架构
+///
+/// # 错误
+///
+/// 架构名无效或者没有启用时返回 [`Error::Config`]
+pub(crate) fn requested_architecture<'a>(config: &InstallConfig, request: &'a str) -> Result<(&'a str, Architecture)> {
+    let (name, arch) = split_qualified_name(request);
+    let arch = match arch {
+        Some(arch) => arch.parse::<Architecture>()?,
+        None => config.architecture,
+    };
+    if !config.architectures().any(|configured| configured == arch) {
+        return Err(Error::Config(format!(
+            "architecture {} is not enabled, add it as a foreign architecture",
+            arch
+        )));
+    }
+    Ok((name, arch))
+}
+
+/// 在包索引中查找请求的包，包名是虚包时选择提供者
+///
+/// # 错误
+///
+/// 虚包有多个提供者且没有首选时返回 [`Error::VirtualPackage`]
+pub(crate) fn find_requested<'a>(
+    config: &InstallConfig,
+    packages: &'a PackageIndex,
+    name: &str,
+    arch: Architecture,
+) -> Result<Option<&'a PackageInfo>> {
+    match find_package(packages, name, arch.as_str()) {
+        Some(info) => Ok(Some(info)),
+        None => select_provider(config, packages, name, arch),
+    }
+}
+
+/// 找不到请求的包时给出提示：包只有其他架构的版本时列出这些架构，否则给出最接近的包名
+pub(crate) fn suggest(packages: &PackageIndex, name: &str) -> Option<String> {
+    let mut architectures: Vec<&str> = packages
+        .get(name)
+        .chain(packages.providers(name))
+        .map(|p| p.architecture.as_str())
+        .collect();
+    if !architectures.is_empty() {
+        architectures.sort_unstable();
+        architectures.dedup();
+        return Some(format!("available for {}", architectures.join(", ")));
+    }
+    // names() 的顺序不固定，排序后保证距离相同时的建议稳定
+    let mut names: Vec<&str> = packages.names().collect();
+    names.sort_unstable();
+    closest_match(name, names).map(|closest| format!("did you mean {}?", closest))
+}
+
+/// 请求的包名是虚包时选择提供者
+///
+/// 只有一个提供者，或者首选的提供者排在第一位时直接使用；有多个提供者时返回
+/// [`Error::VirtualPackage`]，由用户明确选择。
+fn select_provider<'a>(
+    config: &InstallConfig,
+    packages: &'a PackageIndex,
+    name: &str,
+    arch: Architecture,
+) -> Result<Option<&'a PackageInfo>> {
+    let dependency = Dependency {
+        architecture: Some(arch.to_string()),
+        ..Dependency::new(name)
+    };
+    let providers = providers(packages, &dependency, arch.as_str(), &config.resolve_options());
+    match providers.as_slice() {
+        [] => Ok(None),
+        [only] => Ok(Some(only)),
+        [first, ..] if config.preferred_providers.contains(&first.package) => Ok(Some(first)),
+        _ => Err(Error::VirtualPackage {
+            name: name.to_string(),
+            providers: providers.iter().map(|p| p.package.clone()).collect(),
+        }),
+    }
+}
+
+/// 下载并解析一个镜像源的包索引，包括所有启用的架构
+pub(crate) async fn fetch_index(ctx: &SourceContext<'_>, mirror: &str) -> Result<PackageIndex> {
+    let config = ctx.config;
+    let architectures: Vec<Architecture> = config.architectures().collect();
+    let mut target = IndexTarget::new(mirror, &config.suite, &config.components, &architectures);
+    target.freshness = config.freshness;
+    // 只下载时根目录是保存 .deb 的目录，不在其中缓存索引
+    if !config.download_only {
+        target.lists_dir = Some(config.root_dir.join(LISTS_DIR));
+        target.read_only_cache = ctx.read_only;
+    }
+    let content = fetch_packages_file(ctx.downloader, &target, ctx.observer).await?;
+    Ok(parse_packages_file(&content))
+}
+
+/// 已安装的包，只下载时不考虑安装状态
+pub(crate) fn installed_packages(ctx: &SourceContext<'_>) -> Result<PackageIndex> {
+    if ctx.config.download_only {
+        Ok(PackageIndex::new())
+    } else {
+        Ok(StatusDb::load(&ctx.config.root_dir)?.installed_packages())
+    }
+}
+
+/// 把请求的包（以及配置要求时的依赖闭包）加入解析结果
+///
+/// `include_root` 为 `false` 时只加入依赖，用于本地 `.deb` 文件。
+pub(crate) fn add_packages(
+    ctx: &SourceContext<'_>,
+    mirror: &str,
+    packages: &PackageIndex,
+    root: &PackageInfo,
+    include_root: bool,
+    installed: &PackageIndex,
+    resolution: &mut Resolution,
+) -> Result<()> {
+    let config = ctx.config;
+    let selected = if config.resolve_dependencies {
+        dependency_closure(packages, root, installed, &config.resolve_options())?
+    } else {
+        vec![root]
+    };
+    let dest_dir = if config.download_only {
+        config.root_dir.clone()
+    } else {
+        config.root_dir.join(ARCHIVES_DIR)
+    };
+
+    for info in selected {
+        if !include_root && std::ptr::eq(info, root) {
+            continue;
+        }
+        if installed.contains(info) {
+            resolution.skipped.push(SkippedItem {
+                name: info.package.clone(),
+                reason: format!("{} is already installed", info.version),
+            });
+            continue;
+        }
+        // 请求的包记为手动安装，新安装的依赖记为自动安装，升级的包保留原有的标记
+        let arch = effective_architecture(&info.architecture, config.architecture.as_str());
+        let upgrade = installed
+            .get(&info.package)
+            .any(|p| effective_architecture(&p.architecture, config.architecture.as_str()) == arch);
+        let mark = if std::ptr::eq(info, root) {
+            Some(Mark::Manual)
+        } else if upgrade {
+            None
+        } else {
+            Some(Mark::Auto)
+        };
+        resolution.packages.push(ResolvedPackage {
+            name: info.package.clone(),
+            version: info.version.clone(),
+            architecture: info.architecture.clone(),
+            mirror: mirror.to_string(),
+            installed_size: info.installed_size.map(|kib| kib * 1024),
+            mark,
+        });
+        let locations = std::iter::once(&mirror.to_string())
+            .chain(config.mirrors.iter().filter(|m| *m != mirror))
+            .map(|m| ArtifactLocation::new(format!("{}/{}", m, info.filename), m.clone()))
+            .collect();
+        resolution.artifacts.push(Artifact {
+            file_name: info.deb_file_name(),
+            locations,
+            dest_dir: dest_dir.clone(),
+            checksum: Checksum::Sha256(info.sha256.clone()),
+            size: Some(info.size),
+        });
+    }
+    Ok(())
+}
+
+/// 把 `.deb` 文件解包到安装根目录并记录到状态数据库
+pub(crate) async fn install_deb(ctx: &SourceContext<'_>, path: &Path) -> Result<u64> {
+    let root_dir = ctx.config.root_dir.clone();
+    ctx.observer.on_event(&ProgressEvent::ExtractStarted {
+        archive: path.to_path_buf(),
+        destination: root_dir.clone(),
+    });
+    let archive = path.to_path_buf();
+    let entries = tokio::task::spawn_blocking(move || DebArchive::open(&archive)?.install(&root_dir))
+        .await
+        .map_err(|e| Error::Io(std::io::Error::other(e)))??;
+    ctx.observer.on_event(&ProgressEvent::ExtractFinished { archive: path.to_path_buf(), entries });
+    Ok(entries)
+}
@@ -0,0 +1,90 @@
+//! # 本地 .deb 文件来源
+//!
+//! 处理以 `.deb` 结尾的请求，例如 `mini-apt install ./foo.deb`：把本地文件解包到
+//! 安装根目录并记录到状态数据库。配置了
+//! [`resolve_dependencies`](crate::config::InstallConfig::resolve_dependencies) 时，
+//! 尚未安装的依赖从配置的镜像源下载安装。
+
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+
+use super::debian::{add_packages, fetch_index, install_deb, installed_packages};
+use super::{Artifact, PackageSource, Resolution, SourceContext};
+use crate::error::{Error, Result};
+use crate::installer::{InstallError, ResolvedPackage};
+use crate::package::checksum::Checksum;
+use crate::package::deb::DebArchive;
+use crate::package::extended_states::Mark;
+use crate::package::resolver::dependency_closure;
+
+/// 本地 `.deb` 文件
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalDebSource;
+
+impl PackageSource for LocalDebSource {
+    fn name(&self) -> &str {
+        "local-deb"
+    }
+
+    fn handles(&self, request: &str) -> bool {
+        request.ends_with(".deb")
+    }
+
+    fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>> {
+        Box::pin(async move {
+            let path = PathBuf::from(request);
+            let info = DebArchive::open(&path)?.package_info()?;
+            let mut resolution = Resolution {
+                packages: vec![ResolvedPackage {
+                    name: info.package.clone(),
+                    version: info.version.clone(),
+                    architecture: info.architecture.clone(),
+                    mirror: path.display().to_string(),
+                    installed_size: info.installed_size.map(|kib| kib * 1024),
+                    mark: Some(Mark::Manual),
+                }],
+                artifacts: vec![Artifact {
+                    file_name: path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| request.to_string()),
+                    locations: Vec::new(),
+                    dest_dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                    checksum: Checksum::Sha256(info.sha256.clone()),
+                    size: Some(info.size),
+                }],
+                ..Resolution::default()
+            };
+
+            let config = ctx.config;
+            if !config.resolve_dependencies || config.download_only {
+                return Ok(resolution);
+            }
+            let installed = installed_packages(ctx)?;
+            // 已安装的包满足所有依赖时不需要下载索引
+            let missing = match dependency_closure(&Default::default(), &info, &installed, &config.resolve_options()) {
+                Ok(_) => return Ok(resolution),
+                Err(error) => error,
+            };
+            if config.mirrors.is_empty() {
+                return Err(missing);
+            }
+
+            for mirror in &config.mirrors {
+                match fetch_index(ctx, mirror).await {
+                    Ok(packages) => {
+                        add_packages(ctx, mirror, &packages, &info, false, &installed, &mut resolution)?;
+                        return Ok(resolution);
+                    }
+                    Err(error) => resolution.errors.push(InstallError { mirror: Some(mirror.clone()), error }),
+                }
+            }
+            Err(Error::NoMirror("package index".to_string()))
+        })
+    }
+
+    fn unpack<'a>(&'a self, ctx: &'a SourceContext<'a>, _artifact: &'a Artifact, path: &'a Path) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(install_deb(ctx, path))
+    }
+}
@@ -0,0 +1,285 @@
+//! # 外部文件清单
+//!
+//! 这个模块提供 [`ManifestSource`]，按清单文件下载 `.deb` 之外的文件（例如厂商 SDK 的
+//! tar 包），通过共享的下载管理器下载并校验 SHA-256，然后解包到安装根目录下的指定子目录。
+//!
+//! ## 清单格式
+//!
+//! 清单使用与 `Packages` 文件相同的控制文件格式，每个段落描述一个文件：
+//!
+//! ```text
+//! Name: vendor-sdk
+//! Version: 4.2.0
+//! URLs: https://downloads.example.com/vendor-sdk-4.2.0.tar.gz
+//!  https://mirror.example.com/vendor-sdk/vendor-sdk-4.2.0.tar.gz
+//! Size: 73400320
+//! SHA256: 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+//! Archive-Type: tar.gz
+//! Strip-Components: 1
+//! Destination: opt/vendor-sdk
+//! ```
+//!
+//! - `Name`、`URLs` 和 `SHA256` 必须提供，`URLs` 可以用空白或续行分隔多个候选地址
+//! - `Archive-Type` 可以是 `tar`、`tar.gz`、`zip` 或 `file`，省略时根据文件名推断
+//! - `Destination` 是相对于安装根目录的子目录，省略时解包到安装根目录
+//!
+//! 归档下载到安装根目录下的 [`ARTIFACT_CACHE_DIR`]，`file` 类型的文件直接保存到目标目录。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::package::extract::ArchiveType;
+//! use mini_apt::source::manifest::ArtifactManifest;
+//!
+//! let manifest = ArtifactManifest::parse(
+//!     "Name: vendor-sdk\n\
+//!      URLs: https://downloads.example.com/vendor-sdk-4.2.0.tar.gz\n\
+//!      SHA256: 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08\n\
+//!      Strip-Components: 1\n\
+//!      Destination: opt/vendor-sdk\n",
+//! ).unwrap();
+//! let entry = manifest.get("vendor-sdk").unwrap();
+//! assert_eq!(entry.archive_type, ArchiveType::TarGz);
+//! assert_eq!(entry.file_name(), "vendor-sdk-4.2.0.tar.gz");
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+
+use super::{Artifact, ArtifactLocation, PackageSource, Resolution, SourceContext};
+use crate::error::{Error, Result};
+use crate::installer::ResolvedPackage;
+use crate::package::checksum::Checksum;
+use crate::package::control::{parse_stanzas, Stanza};
+use crate::package::extract::{extract_archive, sanitize_path, ArchiveType};
+use crate::progress::ProgressEvent;
+
+/// 归档文件的下载目录，相对于安装根目录
+pub const ARTIFACT_CACHE_DIR: &str = "var/cache/mini-apt/artifacts";
+
+/// 清单中的一个文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// 名称，即 `install` 使用的包名
+    pub name: String,
+    /// 版本号
+    pub version: Option<String>,
+    /// 候选下载地址，按优先级排序
+    pub urls: Vec<String>,
+    /// 文件大小（字节）
+    pub size: Option<u64>,
+    /// SHA-256 校验和
+    pub sha256: String,
+    /// 归档类型
+    pub archive_type: ArchiveType,
+    /// 解包时去掉的目录层数
+    pub strip_components: usize,
+    /// 解包目标子目录，相对于安装根目录
+    pub destination: PathBuf,
+}
+
+impl ManifestEntry {
+    /// 保存的文件名，取第一个下载地址的最后一段路径
+    pub fn file_name(&self) -> String {
+        let url = self.urls.first().map(String::as_str).unwrap_or_default();
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        path.rsplit('/').next().unwrap_or(path).to_string()
+    }
+
+    fn from_stanza(stanza: &Stanza) -> Result<Self> {
+        let name = stanza
+            .get("Name")
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| Error::Parse("manifest entry without Name".to_string()))?
+            .to_string();
+        let field = |key: &str| {
+            stanza
+                .get(key)
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| Error::Parse(format!("manifest entry {}: missing {}", name, key)))
+        };
+
+        let urls: Vec<String> = field("URLs")?.split_whitespace().map(str::to_string).collect();
+        let sha256 = field("SHA256")?.to_ascii_lowercase();
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(Error::Parse(format!("manifest entry {}: invalid SHA256", name)));
+        }
+        let size = stanza
+            .get("Size")
+            .map(|size| size.parse::<u64>())
+            .transpose()
+            .map_err(|_| Error::Parse(format!("manifest entry {}: invalid Size", name)))?;
+        let strip_components = stanza
+            .get("Strip-Components")
+            .map(|strip| strip.parse::<usize>())
+            .transpose()
+            .map_err(|_| Error::Parse(format!("manifest entry {}: invalid Strip-Components", name)))?
+            .unwrap_or(0);
+        let destination = match stanza.get("Destination").filter(|d| !d.is_empty()) {
+            Some(destination) => sanitize_path(Path::new(destination), 0)
+                .map_err(|_| Error::Parse(format!("manifest entry {}: Destination must be a relative path", name)))?
+                .unwrap_or_default(),
+            None => PathBuf::new(),
+        };
+
+        let mut entry = Self {
+            name,
+            version: stanza.get("Version").map(str::to_string),
+            urls,
+            size,
+            sha256,
+            archive_type: ArchiveType::File,
+            strip_components,
+            destination,
+        };
+        entry.archive_type = match stanza.get("Archive-Type") {
+            Some(kind) => kind.parse()?,
+            None => ArchiveType::detect(&entry.file_name()),
+        };
+        Ok(entry)
+    }
+}
+
+/// 外部文件清单
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactManifest {
+    /// 清单中的文件，按出现顺序排列
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl ArtifactManifest {
+    /// 解析清单内容
+    ///
+    /// # 错误
+    ///
+    /// 缺少必需字段、字段值无效或名称重复时返回 [`Error::Parse`]
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut manifest = Self::default();
+        for stanza in parse_stanzas(content) {
+            let entry = ManifestEntry::from_stanza(&stanza)?;
+            if manifest.get(&entry.name).is_some() {
+                return Err(Error::Parse(format!("duplicate manifest entry {}", entry.name)));
+            }
+            manifest.entries.push(entry);
+        }
+        Ok(manifest)
+    }
+
+    /// 读取并解析清单文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io_at(path, e))?;
+        Self::parse(&content).map_err(|e| match e {
+            Error::Parse(message) => Error::Parse(format!("{}: {}", path.display(), message)),
+            other => other,
+        })
+    }
+
+    /// 按名称查找文件
+    pub fn get(&self, name: &str) -> Option<&ManifestEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+}
+
+/// 外部文件来源
+///
+/// 处理名称出现在清单中的请求。
+#[derive(Debug, Clone)]
+pub struct ManifestSource {
+    manifest: ArtifactManifest,
+}
+
+impl ManifestSource {
+    /// 从清单创建来源
+    pub fn new(manifest: ArtifactManifest) -> Self {
+        Self { manifest }
+    }
+
+    /// 使用的清单
+    pub fn manifest(&self) -> &ArtifactManifest {
+        &self.manifest
+    }
+
+    fn entry(&self, request: &str) -> Result<&ManifestEntry> {
+        self.manifest
+            .get(request)
+            .ok_or_else(|| Error::Config(format!("{} is not listed in the artifact manifest", request)))
+    }
+}
+
+/// URL 的 `scheme://host[:port]` 部分，用于报告提供文件的服务器
+fn url_origin(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .map(|parsed| parsed.origin().ascii_serialization())
+        .unwrap_or_else(|_| url.to_string())
+}
+
+impl PackageSource for ManifestSource {
+    fn name(&self) -> &str {
+        "manifest"
+    }
+
+    fn handles(&self, request: &str) -> bool {
+        self.manifest.get(request).is_some()
+    }
+
+    fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>> {
+        Box::pin(async move {
+            let entry = self.entry(request)?;
+            let root_dir = &ctx.config.root_dir;
+            let dest_dir = match entry.archive_type {
+                ArchiveType::File => root_dir.join(&entry.destination),
+                _ => root_dir.join(ARTIFACT_CACHE_DIR),
+            };
+            Ok(Resolution {
+                packages: vec![ResolvedPackage {
+                    name: entry.name.clone(),
+                    version: entry.version.clone().unwrap_or_else(|| "unversioned".to_string()),
+                    architecture: "all".to_string(),
+                    mirror: entry.urls.first().map(|url| url_origin(url)).unwrap_or_default(),
+                    installed_size: None,
+                    mark: None,
+                }],
+                artifacts: vec![Artifact {
+                    file_name: entry.file_name(),
+                    locations: entry
+                        .urls
+                        .iter()
+                        .map(|url| ArtifactLocation::new(url.clone(), url_origin(url)))
+                        .collect(),
+                    dest_dir,
+                    checksum: Checksum::Sha256(entry.sha256.clone()),
+                    size: entry.size,
+                }],
+                ..Resolution::default()
+            })
+        })
+    }
+
+    fn unpack<'a>(&'a self, ctx: &'a SourceContext<'a>, artifact: &'a Artifact, path: &'a Path) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(async move {
+            let entry = self
+                .manifest
+                .entries
+                .iter()
+                .find(|entry| entry.file_name() == artifact.file_name)
+                .ok_or_else(|| Error::Config(format!("{} is not listed in the artifact manifest", artifact.file_name)))?;
+            if entry.archive_type == ArchiveType::File {
+                return Ok(0);
+            }
+
+            let destination = ctx.config.root_dir.join(&entry.destination);
+            ctx.observer.on_event(&ProgressEvent::ExtractStarted {
+                archive: path.to_path_buf(),
+                destination: destination.clone(),
+            });
+            let archive = path.to_path_buf();
+            let (kind, strip) = (entry.archive_type, entry.strip_components);
+            let entries = tokio::task::spawn_blocking(move || extract_archive(&archive, kind, &destination, strip))
+                .await
+                .map_err(|e| Error::Io(std::io::Error::other(e)))??;
+            ctx.observer.on_event(&ProgressEvent::ExtractFinished { archive: path.to_path_buf(), entries });
+            Ok(entries)
+        })
+    }
+}
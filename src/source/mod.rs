@@ -0,0 +1,236 @@
+//! # 软件包来源模块
+//!
+//! 这个模块定义了软件包来源的统一接口 [`PackageSource`]。安装器不再根据包名前缀
+//! 硬编码不同的处理方式，而是依次询问已注册的来源，由第一个能处理该请求的来源完成
+//! 解析、下载、校验和解包。
+//!
+//! ## 主要组件
+//!
+//! - `debian`: Debian/Ubuntu 仓库（默认来源，处理所有其他来源不处理的请求）
+//! - `local`: 本地 `.deb` 文件
+//! - `ndk`: Android NDK 下载、校验和解包
+//! - `manifest`: 按清单文件下载并解包厂商 SDK 等外部文件
+//!
+//! ## 自定义来源
+//!
+//! ```rust
+//! use futures::future::BoxFuture;
+//! use mini_apt::error::Result;
+//! use mini_apt::installer::ResolvedPackage;
+//! use mini_apt::package::checksum::Checksum;
+//! use mini_apt::source::{Artifact, ArtifactLocation, PackageSource, Resolution, SourceContext};
+//!
+//! /// 从公司内部服务器下载固定版本的工具包
+//! struct VendorToolSource;
+//!
+//! impl PackageSource for VendorToolSource {
+//!     fn name(&self) -> &str {
+//!         "vendor-tool"
+//!     }
+//!
+//!     fn handles(&self, request: &str) -> bool {
+//!         request == "vendor-tool"
+//!     }
+//!
+//!     fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>> {
+//!         Box::pin(async move {
+//!             let url = "https://tools.example.com/vendor-tool-1.2.tar.gz";
+//!             Ok(Resolution {
+//!                 packages: vec![ResolvedPackage {
+//!                     name: request.to_string(),
+//!                     version: "1.2".to_string(),
+//!                     architecture: "all".to_string(),
+//!                     mirror: "https://tools.example.com".to_string(),
+//!                     installed_size: None,
+//!                     mark: None,
+//!                 }],
+//!                 artifacts: vec![Artifact {
+//!                     file_name: "vendor-tool-1.2.tar.gz".to_string(),
+//!                     locations: vec![ArtifactLocation::new(url, "https://tools.example.com")],
+//!                     dest_dir: ctx.config.root_dir.clone(),
+//!                     checksum: Checksum::Sha256("0".repeat(64)),
+//!                     size: None,
+//!                 }],
+//!                 ..Resolution::default()
+//!             })
+//!         })
+//!     }
+//! }
+//! ```
+//!
+//! 自定义来源通过 [`InstallerBuilder::source`](crate::installer::InstallerBuilder::source) 注册。
+
+pub mod debian;
+pub mod local;
+pub mod manifest;
+pub mod ndk;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+
+use crate::config::InstallConfig;
+use crate::error::Result;
+use crate::installer::{InstallError, ResolvedPackage, SkippedItem};
+use crate::package::checksum::Checksum;
+use crate::package::downloader::{DownloadManager, DownloadRequest, DownloadedFile};
+use crate::progress::ProgressObserver;
+
+/// 来源执行各个步骤时可以使用的上下文
+pub struct SourceContext<'a> {
+    /// 安装配置
+    pub config: &'a InstallConfig,
+    /// 共享的下载管理器
+    pub downloader: &'a DownloadManager,
+    /// 进度观察者
+    pub observer: &'a dyn ProgressObserver,
+    /// 只解析请求（安装计划和查询），不修改安装根目录中的内容，例如不写入索引缓存
+    pub read_only: bool,
+}
+
+/// 一个可下载文件的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactLocation {
+    /// 文件的完整 URL
+    pub url: String,
+    /// 提供该文件的镜像源（用于报告）
+    pub mirror: String,
+}
+
+impl ArtifactLocation {
+    /// 创建文件位置
+    pub fn new(url: impl Into<String>, mirror: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            mirror: mirror.into(),
+        }
+    }
+}
+
+/// 需要下载的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    /// 保存的文件名
+    pub file_name: String,
+    /// 候选下载位置，按优先级排序；为空表示文件已在本地，不需要下载
+    pub locations: Vec<ArtifactLocation>,
+    /// 保存目录
+    pub dest_dir: PathBuf,
+    /// 预期的校验和
+    pub checksum: Checksum,
+    /// 文件大小（字节）
+    pub size: Option<u64>,
+}
+
+impl Artifact {
+    /// 文件保存的本地路径
+    pub fn path(&self) -> PathBuf {
+        self.dest_dir.join(&self.file_name)
+    }
+}
+
+/// 解析结果
+#[derive(Debug, Default)]
+pub struct Resolution {
+    /// 解析出的软件包
+    pub packages: Vec<ResolvedPackage>,
+    /// 需要下载的文件
+    pub artifacts: Vec<Artifact>,
+    /// 无需处理的项目，例如已经安装的内容
+    pub skipped: Vec<SkippedItem>,
+    /// 解析过程中的非致命错误，例如某个镜像源的索引不可用
+    pub errors: Vec<InstallError>,
+}
+
+/// 软件包来源
+///
+/// 安装一个请求分为四步：
+///
+/// 1. [`resolve`](PackageSource::resolve): 把请求解析为软件包和需要下载的文件
+/// 2. [`fetch`](PackageSource::fetch): 从一个候选位置下载文件，默认通过共享的下载管理器下载并校验
+/// 3. [`verify`](PackageSource::verify): 校验本地已有的文件，通过校验的文件不会重新下载
+/// 4. [`unpack`](PackageSource::unpack): 把文件解包到安装根目录，默认保留原文件不解包
+///
+/// 方法返回 [`BoxFuture`] 以便来源可以作为 trait 对象注册。
+pub trait PackageSource: Send + Sync {
+    /// 来源名称
+    fn name(&self) -> &str;
+
+    /// 是否处理该请求
+    fn handles(&self, request: &str) -> bool;
+
+    /// 解析请求
+    fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>>;
+
+    /// 从指定位置下载文件
+    fn fetch<'a>(
+        &'a self,
+        ctx: &'a SourceContext<'a>,
+        artifact: &'a Artifact,
+        location: &'a ArtifactLocation,
+    ) -> BoxFuture<'a, Result<DownloadedFile>> {
+        Box::pin(async move {
+            let request = DownloadRequest::with_checksum(
+                location.url.clone(),
+                artifact.dest_dir.clone(),
+                artifact.checksum.clone(),
+            )
+            .file_name(artifact.file_name.clone());
+            ctx.downloader.download(&request, ctx.observer).await
+        })
+    }
+
+    /// 校验本地文件
+    fn verify(&self, _ctx: &SourceContext<'_>, artifact: &Artifact, path: &Path) -> Result<()> {
+        artifact.checksum.verify_file(path)
+    }
+
+    /// 解包文件，返回解出的条目数
+    fn unpack<'a>(
+        &'a self,
+        _ctx: &'a SourceContext<'a>,
+        _artifact: &'a Artifact,
+        _path: &'a Path,
+    ) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(async { Ok(0) })
+    }
+}
+
+/// 已注册的软件包来源
+///
+/// 按注册顺序查找，Debian 仓库始终作为最后的后备来源。
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: Vec<Arc<dyn PackageSource>>,
+}
+
+impl Default for SourceRegistry {
+    /// 包含本地 `.deb` 文件、Android NDK 和 Debian 仓库三个内置来源
+    fn default() -> Self {
+        Self {
+            sources: vec![
+                Arc::new(local::LocalDebSource),
+                Arc::new(ndk::NdkSource),
+                Arc::new(debian::DebianSource),
+            ],
+        }
+    }
+}
+
+impl SourceRegistry {
+    /// 注册来源，优先级高于已注册的所有来源
+    pub fn register(&mut self, source: Arc<dyn PackageSource>) {
+        self.sources.insert(0, source);
+    }
+
+    /// 查找处理该请求的来源
+    pub fn find(&self, request: &str) -> Option<&Arc<dyn PackageSource>> {
+        self.sources.iter().find(|source| source.handles(request))
+    }
+
+    /// 所有来源的名称，按查找顺序排列
+    pub fn names(&self) -> Vec<&str> {
+        self.sources.iter().map(|source| source.name()).collect()
+    }
+}
@@ -0,0 +1,240 @@
+//! # Android NDK 模块
+//!
+//! 这个模块提供 [`NdkSource`]，从 Google 的仓库下载 Linux 主机版本的 Android NDK，
+//! 校验固定的 SHA-1，并解包到安装根目录下，之后可以通过 [`NdkInstallation`] 获取工具链和
+//! sysroot 的路径。
+//!
+//! NDK 的 Linux 归档只包含 `linux-x86_64` 主机的预编译工具链。
+//!
+//! ## 示例
+//!
+//! ```rust,no_run
+//! use mini_apt::config::InstallConfig;
+//! use mini_apt::installer::Installer;
+//! use mini_apt::source::ndk::{NdkInstallation, NdkRelease};
+//! use std::path::PathBuf;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = InstallConfig::new(
+//!         "android-ndk-r26b".to_string(),
+//!         vec![],
+//!         "arm64".to_string(),
+//!         PathBuf::from("sysroot"),
+//!     )?;
+//!     Installer::builder(config.clone()).build()?.install().await?;
+//!
+//!     // 编译 android_hello/hello.c:
+//!     //   <clang> --sysroot=<sysroot> android_hello/hello.c -o hello
+//!     let release = NdkRelease::from_package_name(&config.package_name)?;
+//!     let ndk = NdkInstallation::locate(&config.root_dir, &release).expect("NDK installed");
+//!     println!("clang:   {}", ndk.clang("aarch64-linux-android", 21).display());
+//!     println!("sysroot: {}", ndk.sysroot().display());
+//!     Ok(())
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use futures::future::BoxFuture;
+
+use super::{Artifact, ArtifactLocation, PackageSource, Resolution, SourceContext};
+use crate::error::{Error, Result};
+use crate::installer::{ResolvedPackage, SkippedItem};
+use crate::package::checksum::Checksum;
+use crate::package::extract::extract_zip;
+use crate::progress::ProgressEvent;
+
+/// NDK 下载仓库
+pub const NDK_REPOSITORY: &str = "https://dl.google.com/android/repository";
+
+/// 未指定版本时使用的 NDK 版本
+pub const DEFAULT_NDK_VERSION: &str = "r26b";
+
+/// 预编译工具链的主机标签
+pub const NDK_HOST_TAG: &str = "linux-x86_64";
+
+/// 已固定校验和的 NDK 版本：(版本, Linux 归档的 SHA-1, 归档大小)
+///
+/// 数据来自 <https://developer.android.com/ndk/downloads>。
+const PINNED_RELEASES: &[(&str, &str, u64)] = &[
+    ("r25c", "53af80a1cce9144025b81c78c8cd556bff42bd0e", 531_118_193),
+    ("r26b", "fdf33d9f6c1b3f16e5459d53a82c7d2201edbcc4", 668_556_491),
+];
+
+/// 一个 NDK 版本
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdkRelease {
+    /// 版本号，例如 `r26b`
+    pub version: String,
+    /// Linux 归档的校验和
+    pub checksum: Checksum,
+    /// 归档大小（字节）
+    pub size: Option<u64>,
+}
+
+impl NdkRelease {
+    /// 使用内置的固定校验和创建版本
+    ///
+    /// 版本没有固定校验和时返回 `None`，此时需要通过 [`NdkRelease::new`] 提供校验和。
+    pub fn pinned(version: &str) -> Option<Self> {
+        PINNED_RELEASES
+            .iter()
+            .find(|(v, _, _)| *v == version)
+            .map(|(v, sha1, size)| Self {
+                version: v.to_string(),
+                checksum: Checksum::Sha1(sha1.to_string()),
+                size: Some(*size),
+            })
+    }
+
+    /// 使用调用方提供的校验和创建版本
+    pub fn new(version: impl Into<String>, checksum: Checksum) -> Self {
+        Self {
+            version: version.into(),
+            checksum,
+            size: None,
+        }
+    }
+
+    /// 所有已固定校验和的版本号
+    pub fn pinned_versions() -> impl Iterator<Item = &'static str> {
+        PINNED_RELEASES.iter().map(|(v, _, _)| *v)
+    }
+
+    /// 从包名解析版本
+    ///
+    /// `android-ndk` 表示默认版本，`android-ndk-r25c` 表示指定版本。
+    ///
+    /// # 错误
+    ///
+    /// 版本没有固定校验和时返回 [`Error::Config`]
+    pub fn from_package_name(name: &str) -> Result<Self> {
+        let version = match name.strip_prefix("android-ndk") {
+            Some("") => DEFAULT_NDK_VERSION,
+            Some(rest) => rest.strip_prefix('-').unwrap_or(rest),
+            None => return Err(Error::Config(format!("{} is not an Android NDK package", name))),
+        };
+        Self::pinned(version).ok_or_else(|| {
+            Error::Config(format!(
+                "no pinned checksum for Android NDK {}; known versions: {}",
+                version,
+                Self::pinned_versions().collect::<Vec<_>>().join(", ")
+            ))
+        })
+    }
+
+    /// Linux 归档的文件名
+    pub fn archive_name(&self) -> String {
+        format!("android-ndk-{}-linux.zip", self.version)
+    }
+
+    /// Linux 归档的下载 URL
+    pub fn url(&self) -> String {
+        format!("{}/{}", NDK_REPOSITORY, self.archive_name())
+    }
+}
+
+/// 已解包的 NDK
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdkInstallation {
+    /// NDK 根目录，例如 `sysroot/android-ndk-r26b`
+    pub root: PathBuf,
+}
+
+impl NdkInstallation {
+    /// 在安装根目录中查找已解包的 NDK
+    ///
+    /// 以 NDK 根目录下的 `source.properties` 作为解包完成的标志。
+    pub fn locate(root_dir: &Path, release: &NdkRelease) -> Option<Self> {
+        let root = root_dir.join(format!("android-ndk-{}", release.version));
+        root.join("source.properties").is_file().then_some(Self { root })
+    }
+
+    /// LLVM 工具链目录
+    pub fn toolchain_dir(&self) -> PathBuf {
+        self.root.join("toolchains").join("llvm").join("prebuilt").join(NDK_HOST_TAG)
+    }
+
+    /// 工具链的 `bin` 目录
+    pub fn bin_dir(&self) -> PathBuf {
+        self.toolchain_dir().join("bin")
+    }
+
+    /// 工具链自带的 sysroot
+    pub fn sysroot(&self) -> PathBuf {
+        self.toolchain_dir().join("sysroot")
+    }
+
+    /// 针对指定目标和 API 级别的 clang 包装脚本
+    ///
+    /// 例如 `clang("aarch64-linux-android", 21)` 返回
+    /// `.../bin/aarch64-linux-android21-clang`。
+    pub fn clang(&self, target: &str, api_level: u32) -> PathBuf {
+        self.bin_dir().join(format!("{}{}-clang", target, api_level))
+    }
+}
+
+/// Android NDK 来源
+///
+/// 处理以 `android-ndk` 开头的请求。已解包的版本会被跳过，归档解包到安装根目录。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NdkSource;
+
+impl PackageSource for NdkSource {
+    fn name(&self) -> &str {
+        "android-ndk"
+    }
+
+    fn handles(&self, request: &str) -> bool {
+        request.starts_with("android-ndk")
+    }
+
+    fn resolve<'a>(&'a self, ctx: &'a SourceContext<'a>, request: &'a str) -> BoxFuture<'a, Result<Resolution>> {
+        Box::pin(async move {
+            let release = NdkRelease::from_package_name(request)?;
+            let root_dir = &ctx.config.root_dir;
+            let mut resolution = Resolution {
+                packages: vec![ResolvedPackage {
+                    name: "android-ndk".to_string(),
+                    version: release.version.clone(),
+                    architecture: NDK_HOST_TAG.to_string(),
+                    mirror: NDK_REPOSITORY.to_string(),
+                    installed_size: None,
+                    mark: None,
+                }],
+                ..Resolution::default()
+            };
+            match NdkInstallation::locate(root_dir, &release) {
+                Some(installation) => resolution.skipped.push(SkippedItem {
+                    name: release.archive_name(),
+                    reason: format!("already installed at {}", installation.root.display()),
+                }),
+                None => resolution.artifacts.push(Artifact {
+                    file_name: release.archive_name(),
+                    locations: vec![ArtifactLocation::new(release.url(), NDK_REPOSITORY)],
+                    dest_dir: root_dir.clone(),
+                    checksum: release.checksum.clone(),
+                    size: release.size,
+                }),
+            }
+            Ok(resolution)
+        })
+    }
+
+    fn unpack<'a>(&'a self, ctx: &'a SourceContext<'a>, _artifact: &'a Artifact, path: &'a Path) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(async move {
+            let root_dir = ctx.config.root_dir.clone();
+            ctx.observer.on_event(&ProgressEvent::ExtractStarted {
+                archive: path.to_path_buf(),
+                destination: root_dir.clone(),
+            });
+            let archive = path.to_path_buf();
+            let entries = tokio::task::spawn_blocking(move || extract_zip(&archive, &root_dir, 0))
+                .await
+                .map_err(|e| Error::Io(std::io::Error::other(e)))??;
+            ctx.observer.on_event(&ProgressEvent::ExtractFinished { archive: path.to_path_buf(), entries });
+            Ok(entries)
+        })
+    }
+}
@@ -0,0 +1,280 @@
+//! # 仓库认证模块
+//!
+//! 这个模块解析 APT 的 `auth.conf` 凭据文件（netrc 格式），为访问需要认证的私有仓库
+//! 提供用户名和密码。
+//!
+//! 文件由 `machine`、`login`、`password` 三个关键字组成的条目构成，关键字和值之间用空白
+//! 分隔，一个条目可以跨越多行，`#` 开头的行是注释：
+//!
+//! ```text
+//! machine internal.example.com/debian/ login ci password s3cret
+//! machine http://legacy.example.com
+//!     login ci
+//!     password s3cret
+//! ```
+//!
+//! 匹配规则与 APT 相同：
+//!
+//! - `machine` 可以包含端口和路径，路径按前缀匹配
+//! - 带协议的 `machine` 只匹配该协议；不带协议时只匹配 `https`，避免密码以明文传输
+//! - 按文件中的顺序，第一个匹配的条目生效
+//!
+//! 凭据的 `Debug` 输出会隐藏密码，库不会在任何日志或错误信息中包含凭据。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::utils::auth::AuthConf;
+//!
+//! let auth = AuthConf::parse("machine repo.example.com/private login ci password s3cret").unwrap();
+//! let credentials = auth.credentials_for("https://repo.example.com/private/dists/focal/Release").unwrap();
+//! assert_eq!(credentials.login, "ci");
+//! assert!(auth.credentials_for("https://repo.example.com/public/Release").is_none());
+//! assert!(auth.credentials_for("http://repo.example.com/private/Release").is_none());
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// 用户名和密码
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// 用户名
+    pub login: String,
+    /// 密码
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("login", &self.login)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// `auth.conf` 中的一个条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthEntry {
+    /// URL 协议，`None` 表示只匹配 `https`
+    pub scheme: Option<String>,
+    /// 主机名，可以带端口
+    pub host: String,
+    /// 路径前缀
+    pub path: String,
+    /// 凭据
+    pub credentials: Credentials,
+}
+
+impl AuthEntry {
+    fn matches(&self, url: &reqwest::Url) -> bool {
+        let scheme_matches = match &self.scheme {
+            Some(s) => s == url.scheme(),
+            None => url.scheme() == "https",
+        };
+        let Some(host) = url.host_str() else { return false };
+        // 带端口的条目与 URL 的实际端口比较，URL 省略端口时使用协议的默认端口
+        let port = self.host.rsplit_once(':').filter(|(_, port)| port.bytes().all(|b| b.is_ascii_digit()));
+        let host_matches = match port {
+            Some((name, port)) => {
+                name == host && url.port_or_known_default().is_some_and(|p| p.to_string() == port)
+            }
+            None => self.host == host && url.port().is_none(),
+        };
+        scheme_matches && host_matches && url.path().starts_with(&self.path)
+    }
+}
+
+/// 解析后的凭据文件
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthConf {
+    entries: Vec<AuthEntry>,
+}
+
+impl AuthConf {
+    /// 解析凭据文件内容
+    ///
+    /// # 错误
+    ///
+    /// 出现未知关键字、关键字缺少值或条目缺少 `login`/`password` 时返回 [`Error::Parse`]。
+    /// 错误信息只包含条目的 `machine`，不包含凭据。
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut tokens = content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace);
+
+        let mut entries = Vec::new();
+        let mut current: Option<(String, Option<String>, Option<String>)> = None;
+        while let Some(keyword) = tokens.next() {
+            let value = tokens
+                .next()
+                .ok_or_else(|| Error::Parse(format!("auth.conf: missing value for '{}'", keyword)))?;
+            match keyword {
+                "machine" => {
+                    if let Some(entry) = current.take() {
+                        entries.push(Self::finish_entry(entry)?);
+                    }
+                    current = Some((value.to_string(), None, None));
+                }
+                "login" | "password" => {
+                    let entry = current
+                        .as_mut()
+                        .ok_or_else(|| Error::Parse(format!("auth.conf: '{}' before any 'machine'", keyword)))?;
+                    if keyword == "login" {
+                        entry.1 = Some(value.to_string());
+                    } else {
+                        entry.2 = Some(value.to_string());
+                    }
+                }
+                // 不回显未知的词，它可能是错位的密码
+                _ => {
+                    let machine = current.as_ref().map(|c| c.0.as_str()).unwrap_or("<none>");
+                    return Err(Error::Parse(format!("auth.conf: unexpected token in entry for machine {}", machine)));
+                }
+            }
+        }
+        if let Some(entry) = current {
+            entries.push(Self::finish_entry(entry)?);
+        }
+        Ok(Self { entries })
+    }
+
+    fn finish_entry((machine, login, password): (String, Option<String>, Option<String>)) -> Result<AuthEntry> {
+        let (login, password) = match (login, password) {
+            (Some(login), Some(password)) => (login, password),
+            _ => {
+                return Err(Error::Parse(format!(
+                    "auth.conf: machine {} needs both login and password",
+                    machine
+                )))
+            }
+        };
+        let (scheme, rest) = match machine.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_string()), rest),
+            None => (None, machine.as_str()),
+        };
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        Ok(AuthEntry {
+            scheme,
+            host: host.to_string(),
+            path: path.to_string(),
+            credentials: Credentials { login, password },
+        })
+    }
+
+    /// 读取单个凭据文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::io_at(path, e))?;
+        Self::parse(&content).map_err(|e| match e {
+            Error::Parse(message) => Error::Parse(format!("{}: {}", path.display(), message)),
+            other => other,
+        })
+    }
+
+    /// 读取 APT 的默认凭据文件
+    ///
+    /// 依次读取 `<etc_apt>/auth.conf` 和 `<etc_apt>/auth.conf.d/*.conf`（按文件名排序）。
+    /// 默认文件通常只有 root 可以读取，因此不存在的文件会被忽略，无法读取或解析的文件
+    /// 会被跳过，不影响其他文件。
+    ///
+    /// # 返回值
+    ///
+    /// 读取到的凭据，以及被跳过的文件对应的错误（供调用者作为警告输出）
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use mini_apt::utils::auth::AuthConf;
+    /// use std::fs;
+    ///
+    /// let etc_apt = std::env::temp_dir().join("mini-apt-doctest-auth-defaults");
+    /// let _ = fs::remove_dir_all(&etc_apt);
+    /// fs::create_dir_all(etc_apt.join("auth.conf.d")).unwrap();
+    /// // 用目录代替权限为 0600 的文件，以 root 运行时同样无法读取
+    /// fs::create_dir(etc_apt.join("auth.conf")).unwrap();
+    /// fs::write(etc_apt.join("auth.conf.d/ci.conf"), "machine repo.example.com login ci password s3cret").unwrap();
+    ///
+    /// let (auth, warnings) = AuthConf::load_apt_defaults(&etc_apt);
+    /// assert_eq!(warnings.len(), 1);
+    /// assert!(warnings[0].to_string().contains("auth.conf"));
+    /// assert_eq!(auth.credentials_for("https://repo.example.com/Release").unwrap().login, "ci");
+    ///
+    /// // 默认文件都不存在时没有警告
+    /// let (auth, warnings) = AuthConf::load_apt_defaults(&etc_apt.join("missing"));
+    /// assert!(auth.is_empty() && warnings.is_empty());
+    /// fs::remove_dir_all(&etc_apt).unwrap();
+    /// ```
+    pub fn load_apt_defaults(etc_apt: &Path) -> (Self, Vec<Error>) {
+        let mut conf = Self::default();
+        let mut warnings = Vec::new();
+        let mut files = vec![etc_apt.join("auth.conf")];
+        if let Ok(read_dir) = std::fs::read_dir(etc_apt.join("auth.conf.d")) {
+            let mut parts: Vec<_> = read_dir
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "conf"))
+                .collect();
+            parts.sort();
+            files.extend(parts);
+        }
+        for file in files {
+            match Self::load(&file) {
+                Ok(other) => conf.extend(other),
+                Err(Error::IoAt { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => warnings.push(e),
+            }
+        }
+        (conf, warnings)
+    }
+
+    /// 追加另一个凭据文件的条目（优先级较低）
+    pub fn extend(&mut self, other: AuthConf) {
+        self.entries.extend(other.entries);
+    }
+
+    /// 所有条目
+    pub fn entries(&self) -> &[AuthEntry] {
+        &self.entries
+    }
+
+    /// 是否没有任何条目
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 查找适用于 URL 的凭据
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use mini_apt::utils::auth::AuthConf;
+    ///
+    /// let auth = AuthConf::parse(
+    ///     "machine mirror.example.com:443/ubuntu login tls password a\n\
+    ///      machine http://mirror.example.com:8080 login plain password b",
+    /// ).unwrap();
+    /// let login = |url| auth.credentials_for(url).map(|c| c.login.as_str());
+    ///
+    /// // 条目中的端口与 URL 省略的默认端口相同
+    /// assert_eq!(login("https://mirror.example.com/ubuntu/dists/focal/Release"), Some("tls"));
+    /// assert_eq!(login("https://mirror.example.com:443/ubuntu/pool/a.deb"), Some("tls"));
+    /// assert_eq!(login("https://mirror.example.com:8443/ubuntu/pool/a.deb"), None);
+    /// // 路径按前缀匹配
+    /// assert_eq!(login("https://mirror.example.com/debian/Release"), None);
+    /// assert_eq!(login("http://mirror.example.com:8080/any/path"), Some("plain"));
+    /// assert_eq!(login("http://mirror.example.com/any/path"), None);
+    /// ```
+    pub fn credentials_for(&self, url: &str) -> Option<&Credentials> {
+        let url = reqwest::Url::parse(url).ok()?;
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(&url))
+            .map(|entry| &entry.credentials)
+    }
+}
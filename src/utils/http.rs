@@ -0,0 +1,166 @@
+//! # HTTP 客户端配置模块
+//!
+//! 这个模块集中处理 HTTP 客户端的网络配置，包括代理、额外的 CA 证书、超时、传输停滞检测
+//! 以及私有仓库的认证凭据。
+//! 包索引和软件包下载都通过 [`build_client`] 创建的客户端进行，因此只需要在
+//! [`InstallConfig`](crate::config::InstallConfig) 中配置一次。
+//!
+//! ## 代理
+//!
+//! - `proxy` 为 `None` 时，使用环境变量 `http_proxy`、`https_proxy`、`no_proxy`
+//!   （以及对应的大写形式）
+//! - `proxy` 为 `Some` 时，只使用显式配置，忽略环境变量；空配置表示直接连接
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::utils::http::{build_client, HttpOptions, ProxySettings, StallDetection};
+//! use std::time::Duration;
+//!
+//! let options = HttpOptions {
+//!     proxy: Some(ProxySettings {
+//!         http: Some("http://proxy.corp.example:3128".to_string()),
+//!         https: Some("http://proxy.corp.example:3128".to_string()),
+//!         no_proxy: vec!["mirror.corp.example".to_string()],
+//!     }),
+//!     connect_timeout: Some(Duration::from_secs(10)),
+//!     stall: Some(StallDetection { min_bytes_per_sec: 1024, window: Duration::from_secs(30) }),
+//!     ..HttpOptions::default()
+//! };
+//! let client = build_client(&options).unwrap();
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+
+use crate::error::{Error, Result};
+use crate::package::downloader::USER_AGENT;
+use crate::utils::auth::AuthConf;
+
+/// 代理配置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxySettings {
+    /// HTTP 请求使用的代理
+    pub http: Option<String>,
+    /// HTTPS 请求使用的代理
+    pub https: Option<String>,
+    /// 不使用代理的主机列表（语法与 `no_proxy` 环境变量相同）
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    /// 从 `http_proxy`、`https_proxy`、`no_proxy` 环境变量读取代理配置
+    ///
+    /// 小写形式优先，与 APT 和 curl 的行为一致。
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .or_else(|_| std::env::var(name.to_uppercase()))
+                .ok()
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            http: var("http_proxy"),
+            https: var("https_proxy"),
+            no_proxy: var("no_proxy")
+                .map(|v| v.split(',').map(|h| h.trim().to_string()).filter(|h| !h.is_empty()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 是否没有配置任何代理
+    pub fn is_direct(&self) -> bool {
+        self.http.is_none() && self.https.is_none()
+    }
+}
+
+/// 传输停滞检测
+///
+/// 与 curl 的 `--speed-limit`/`--speed-time` 相同：如果在 `window` 时间内的平均速度
+/// 低于 `min_bytes_per_sec`，下载将被中止。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StallDetection {
+    /// 最低平均速度（字节/秒）
+    pub min_bytes_per_sec: u64,
+    /// 统计窗口
+    pub window: Duration,
+}
+
+/// HTTP 客户端选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HttpOptions {
+    /// 显式代理配置，`None` 表示使用环境变量
+    pub proxy: Option<ProxySettings>,
+    /// 额外信任的 CA 证书文件（PEM 格式，可以包含多个证书）
+    pub ca_certificates: Vec<PathBuf>,
+    /// 建立连接的超时时间
+    pub connect_timeout: Option<Duration>,
+    /// 等待响应头或下一段数据的超时时间
+    pub read_timeout: Option<Duration>,
+    /// 传输停滞检测
+    pub stall: Option<StallDetection>,
+    /// 私有仓库的认证凭据，同时用于包索引和软件包下载
+    pub auth: AuthConf,
+}
+
+/// 根据选项创建 HTTP 客户端
+///
+/// # 错误
+///
+/// 代理地址无效、证书文件无法读取或解析时返回 [`Error::Config`]
+pub fn build_client(options: &HttpOptions) -> Result<Client> {
+    let mut builder = Client::builder().user_agent(USER_AGENT);
+
+    // 始终使用解析后的代理配置，关闭 reqwest 自带的环境变量处理，
+    // 保证显式配置和环境变量的语义一致
+    let proxy = options.proxy.clone().unwrap_or_else(ProxySettings::from_env);
+    builder = builder.no_proxy();
+    let no_proxy = NoProxy::from_string(&proxy.no_proxy.join(","));
+    if let Some(url) = &proxy.http {
+        let http = Proxy::http(url.as_str())
+            .map_err(|e| Error::Config(format!("invalid HTTP proxy {}: {}", url, e)))?;
+        builder = builder.proxy(http.no_proxy(no_proxy.clone()));
+    }
+    if let Some(url) = &proxy.https {
+        let https = Proxy::https(url.as_str())
+            .map_err(|e| Error::Config(format!("invalid HTTPS proxy {}: {}", url, e)))?;
+        builder = builder.proxy(https.no_proxy(no_proxy));
+    }
+
+    for path in &options.ca_certificates {
+        for certificate in load_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if let Some(timeout) = options.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+
+    builder
+        .build()
+        .map_err(|e| Error::Config(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// 读取 PEM 文件中的所有证书
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    let pem = std::fs::read_to_string(path).map_err(|e| Error::io_at(path, e))?;
+    let certificates = pem
+        .match_indices(BEGIN)
+        .map(|(start, _)| {
+            let block = &pem[start..];
+            let end = block.find("-----END CERTIFICATE-----")
+                .map(|i| i + "-----END CERTIFICATE-----".len())
+                .unwrap_or(block.len());
+            Certificate::from_pem(&block.as_bytes()[..end])
+                .map_err(|e| Error::Config(format!("invalid certificate in {}: {}", path.display(), e)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if certificates.is_empty() {
+        return Err(Error::Config(format!("no certificates found in {}", path.display())));
+    }
+    Ok(certificates)
+}
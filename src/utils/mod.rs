@@ -1 +1,4 @@
-pub mod url; 
\ No newline at end of file
+pub mod auth;
+pub mod http;
+pub mod text;
+pub mod url;
\ No newline at end of file
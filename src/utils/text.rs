@@ -0,0 +1,66 @@
+//! # 文本工具模块
+//!
+//! 这个模块提供拼写建议（"did you mean"）所需的编辑距离计算。
+//!
+//! ## 示例
+//!
+//! ```rust
+//! use mini_apt::utils::text::{closest_match, edit_distance};
+//!
+//! assert_eq!(edit_distance("amd46", "amd64"), 1);
+//! assert_eq!(closest_match("amd46", ["arm64", "amd64", "i386"]), Some("amd64"));
+//! assert_eq!(closest_match("sparc", ["arm64", "amd64", "i386"]), None);
+//! ```
+
+/// 两个字符串之间的编辑距离（按字符计算）
+///
+/// 插入、删除、替换和相邻字符交换各计 1，即 optimal string alignment 距离。
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] 是 a[..i] 与 b[..j] 的距离
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = distance;
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// 候选项中与 `input` 最接近的一个
+///
+/// 只有编辑距离不超过 `input` 长度的三分之一（至少为 1）时才认为是拼写错误，
+/// 距离相同时取先出现的候选项。
+///
+/// # 参数
+///
+/// * `input` - 用户输入
+/// * `candidates` - 候选项
+///
+/// # 返回值
+///
+/// 足够接近的候选项，没有时返回 `None`
+pub fn closest_match<'a, I>(input: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let limit = (input.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
@@ -0,0 +1,122 @@
+//! `autoremove`：哪些自动安装的包可以删除，以及删除后的标记
+
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+use common::{build_deb, temp_dir};
+use mini_apt::package::deb::DebArchive;
+use mini_apt::package::explain::autoremovable;
+use mini_apt::package::extended_states::{ExtendedStates, Mark};
+use mini_apt::package::status::StatusDb;
+
+/// `app` 手动安装；`lib` 被 `app` 依赖，`plugin` 被 `app` 推荐，`docs` 只被 `app` 建议，
+/// `helper` 只被 `docs` 依赖，`old` 不被任何包需要；`tool` 手动安装且不被需要
+const PACKAGES: [(&str, &str); 7] = [
+    ("app", "Depends: lib\nRecommends: plugin\nSuggests: docs\n"),
+    ("lib", ""),
+    ("plugin", ""),
+    ("docs", "Depends: helper\n"),
+    ("helper", ""),
+    ("old", ""),
+    ("tool", ""),
+];
+
+const AUTO: [&str; 6] = ["lib", "plugin", "docs", "helper", "old", "tool"];
+
+/// 在 `root` 中安装 [`PACKAGES`]，并把 `auto` 中的包标记为自动安装
+fn setup(name: &str, auto: &[&str]) -> (std::path::PathBuf, std::path::PathBuf) {
+    let dir = temp_dir(name);
+    let root = dir.join("root");
+    for (package, relations) in PACKAGES {
+        let control = format!("Package: {}\nVersion: 1\nArchitecture: arm64\n{}", package, relations);
+        let file = format!("usr/share/{}/file", package);
+        DebArchive::open(&build_deb(&dir, &control, &[(&file, package)])).unwrap().install(&root).unwrap();
+    }
+    ExtendedStates::update(&root, |states| {
+        for package in auto {
+            states.set(package, "arm64", Mark::Auto);
+        }
+        Ok(())
+    })
+    .unwrap();
+    (dir, root)
+}
+
+fn removable(root: &Path) -> Vec<String> {
+    let installed = StatusDb::load(root).unwrap().installed_packages();
+    let states = ExtendedStates::load(root).unwrap();
+    autoremovable(&installed, &states, "arm64").iter().map(|p| p.package.clone()).collect()
+}
+
+fn autoremove(root: &Path, dry_run: bool) -> String {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_mini-apt"));
+    command.args(["autoremove", "-m", "arm64", "-d"]).arg(root);
+    if dry_run {
+        command.arg("--dry-run");
+    }
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn keeps_what_manual_packages_need() {
+    let auto: Vec<&str> = AUTO.iter().copied().filter(|p| *p != "tool").collect();
+    let (dir, root) = setup("autoremove-needed", &auto);
+    // Depends 和 Recommends 使包保留，Suggests 不算；手动安装的包即使不被需要也保留
+    assert_eq!(removable(&root), ["docs", "helper", "old"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn nothing_to_remove_without_marks() {
+    let (dir, root) = setup("autoremove-unmarked", &[]);
+    assert!(removable(&root).is_empty());
+    assert_eq!(autoremove(&root, false), "No packages to remove\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn dry_run_removes_nothing() {
+    let (dir, root) = setup("autoremove-dry-run", &["old"]);
+    let before = std::fs::read_to_string(root.join("var/lib/dpkg/status")).unwrap();
+    let output = autoremove(&root, true);
+    assert_eq!(output, "The following packages will be REMOVED:\n  old 1 (arm64)\n");
+    assert_eq!(std::fs::read_to_string(root.join("var/lib/dpkg/status")).unwrap(), before);
+    assert!(root.join("usr/share/old/file").exists());
+    assert!(ExtendedStates::load(&root).unwrap().is_auto("old", "arm64"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn removal_forgets_the_mark() {
+    let (dir, root) = setup("autoremove-forget", &["old", "lib"]);
+    let output = autoremove(&root, false);
+    assert!(output.ends_with("Removed old 1 (1 files)\n"), "{}", output);
+    assert!(!root.join("usr/share/old").exists());
+    assert!(StatusDb::load(&root).unwrap().get("old").is_none());
+    let states = ExtendedStates::load(&root).unwrap();
+    assert!(!states.is_auto("old", "arm64"));
+    assert!(states.is_auto("lib", "arm64"));
+    let content = std::fs::read_to_string(root.join("var/lib/apt/extended_states")).unwrap();
+    assert!(!content.contains("Package: old"), "{}", content);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn forget_only_affects_one_architecture() {
+    let dir = temp_dir("autoremove-states");
+    ExtendedStates::update(&dir, |states| {
+        states.set("libc6", "arm64", Mark::Auto);
+        states.set("libc6", "armhf", Mark::Auto);
+        states.forget("libc6", "armhf");
+        Ok(())
+    })
+    .unwrap();
+    let states = ExtendedStates::load(&dir).unwrap();
+    assert!(states.is_auto("libc6", "arm64"));
+    assert!(!states.is_auto("libc6", "armhf"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
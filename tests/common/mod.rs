@@ -0,0 +1,165 @@
+//! 集成测试共用的工具：临时目录、本地 HTTP 服务器和 `.deb` 构造
+
+#![allow(dead_code)]
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use mini_apt::package::control::parse_stanzas;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// 为测试创建一个空的临时目录
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mini-apt-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 只提供静态文件的 HTTP 服务器，找不到的路径返回 404
+///
+/// 每个连接只处理一个请求，并记录同时处理的连接数的最大值。
+pub struct TestServer {
+    /// 服务器地址，例如 `http://127.0.0.1:40000`
+    pub url: String,
+    /// 路径（以 `/` 开头）到内容的映射，测试过程中可以修改
+    pub files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    active: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl TestServer {
+    /// 启动服务器，每个响应在发送前等待 `delay`
+    pub async fn start(delay: Duration) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = Self {
+            url: format!("http://127.0.0.1:{}", port),
+            files: Arc::default(),
+            active: Arc::default(),
+            peak: Arc::default(),
+        };
+        let (files, active, peak) = (server.files.clone(), server.active.clone(), server.peak.clone());
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else { break };
+                let (files, active, peak) = (files.clone(), active.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request);
+                    let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                    tokio::time::sleep(delay).await;
+                    let body = files.lock().unwrap().get(&path).cloned();
+                    let response = match body {
+                        Some(body) => {
+                            let mut response =
+                                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len())
+                                    .into_bytes();
+                            response.extend_from_slice(&body);
+                            response
+                        }
+                        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+                    };
+                    let _ = stream.write_all(&response).await;
+                    let _ = stream.shutdown().await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        server
+    }
+
+    /// 添加或替换一个文件
+    pub fn insert(&self, path: &str, content: impl Into<Vec<u8>>) {
+        self.files.lock().unwrap().insert(path.to_string(), content.into());
+    }
+
+    /// 删除一个文件
+    pub fn remove(&self, path: &str) {
+        self.files.lock().unwrap().remove(path);
+    }
+
+    /// 同时处理的连接数的最大值
+    pub fn peak_connections(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+/// 十六进制的 SHA-256 校验和
+pub fn sha256_hex(content: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(content))
+}
+
+/// 构造一个 `.deb`，`files` 为 `(路径, 内容)`，路径不带开头的 `./`
+pub fn build_deb(dir: &Path, control: &str, files: &[(&str, &str)]) -> PathBuf {
+    fn tar_gz(entries: impl FnOnce(&mut tar::Builder<flate2::write::GzEncoder<Vec<u8>>>)) -> Vec<u8> {
+        let mut builder =
+            tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        entries(&mut builder);
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+    fn append(builder: &mut tar::Builder<flate2::write::GzEncoder<Vec<u8>>>, path: &str, content: Option<&str>) {
+        let mut header = tar::Header::new_gnu();
+        match content {
+            Some(content) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(0o644);
+                header.set_size(content.len() as u64);
+            }
+            None => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(0o755);
+                header.set_size(0);
+            }
+        }
+        builder.append_data(&mut header, path, content.unwrap_or("").as_bytes()).unwrap();
+    }
+
+    let control_tar = tar_gz(|builder| append(builder, "./control", Some(control)));
+    let directories: BTreeSet<String> = files
+        .iter()
+        .flat_map(|(path, _)| Path::new(path).ancestors().skip(1).map(|dir| dir.to_string_lossy().into_owned()))
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    let data_tar = tar_gz(|builder| {
+        for directory in &directories {
+            append(builder, &format!("./{}/", directory), None);
+        }
+        for (path, content) in files {
+            append(builder, &format!("./{}", path), Some(content));
+        }
+    });
+
+    let stanza = parse_stanzas(control).remove(0);
+    let name = format!(
+        "{}_{}_{}.deb",
+        stanza.get("Package").unwrap(),
+        stanza.get("Version").unwrap(),
+        stanza.get("Architecture").unwrap()
+    );
+    let path = dir.join(name);
+    let mut builder = ar::Builder::new(std::fs::File::create(&path).unwrap());
+    for (member, content) in [
+        ("debian-binary", b"2.0\n".to_vec()),
+        ("control.tar.gz", control_tar),
+        ("data.tar.gz", data_tar),
+    ] {
+        let header = ar::Header::new(member.as_bytes().to_vec(), content.len() as u64);
+        builder.append(&header, content.as_slice()).unwrap();
+    }
+    path
+}
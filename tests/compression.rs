@@ -0,0 +1,126 @@
+//! 各压缩格式的解压，以及未启用的压缩格式的报错
+//!
+//! 用 `cargo test --no-default-features` 运行可以覆盖未启用 feature 的情况。
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use mini_apt::package::compression::Compression;
+use mini_apt::package::deb::DebArchive;
+
+const TEXT: &str = "Package: hello\nVersion: 1.0\nArchitecture: arm64\n";
+
+/// 解压 `compressed`，并检查结果为 `expected`
+fn assert_decodes(compression: Compression, compressed: &[u8], expected: &str) {
+    let mut decoded = String::new();
+    compression
+        .decoder(compressed)
+        .unwrap()
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, expected, "{}", compression);
+}
+
+/// 解压时应返回指明 feature 名的错误
+#[cfg(not(all(feature = "xz", feature = "zstd", feature = "bzip2")))]
+fn assert_unsupported(compression: Compression, feature: &str) {
+    assert!(!compression.is_supported());
+    match compression.decoder(&b""[..]) {
+        Err(mini_apt::error::Error::Parse(message)) => {
+            assert!(message.contains(&format!("built without the \"{}\" feature", feature)), "{}", message)
+        }
+        Err(other) => panic!("unexpected error: {}", other),
+        Ok(_) => panic!("{} should not be supported", compression),
+    }
+}
+
+#[test]
+fn gzip_and_uncompressed() {
+    assert_decodes(Compression::None, TEXT.as_bytes(), TEXT);
+
+    let gzip = |text: &str| {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    };
+    assert_decodes(Compression::Gzip, &gzip(TEXT), TEXT);
+    // 首尾相接的多个压缩流
+    assert_decodes(Compression::Gzip, &[gzip("a\n"), gzip("b\n")].concat(), "a\nb\n");
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn xz_round_trip() {
+    let xz = |text: &str| {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    };
+    assert!(Compression::Xz.is_supported());
+    assert_decodes(Compression::Xz, &xz(TEXT), TEXT);
+    assert_decodes(Compression::Xz, &[xz("a\n"), xz("b\n")].concat(), "a\nb\n");
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_round_trip() {
+    let zstd = |text: &str| zstd::stream::encode_all(text.as_bytes(), 0).unwrap();
+    assert!(Compression::Zstd.is_supported());
+    assert_decodes(Compression::Zstd, &zstd(TEXT), TEXT);
+    assert_decodes(Compression::Zstd, &[zstd("a\n"), zstd("b\n")].concat(), "a\nb\n");
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn bzip2_round_trip() {
+    let bzip2 = |text: &str| {
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    };
+    assert!(Compression::Bzip2.is_supported());
+    assert_decodes(Compression::Bzip2, &bzip2(TEXT), TEXT);
+    assert_decodes(Compression::Bzip2, &[bzip2("a\n"), bzip2("b\n")].concat(), "a\nb\n");
+}
+
+#[cfg(not(feature = "xz"))]
+#[test]
+fn xz_disabled() {
+    assert_unsupported(Compression::Xz, "xz");
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_disabled() {
+    assert_unsupported(Compression::Zstd, "zstd");
+}
+
+#[cfg(not(feature = "bzip2"))]
+#[test]
+fn bzip2_disabled() {
+    assert_unsupported(Compression::Bzip2, "bzip2");
+}
+
+/// 成员为 `control.tar.xz` 和 `data.tar.xz` 的真实 `.deb`
+const XZ_DEB: &str = "sysroot/cpp-x86-64-linux-gnu_9.3.0-1ubuntu2_arm64.deb";
+
+#[cfg(feature = "xz")]
+#[test]
+fn reads_xz_compressed_deb() {
+    let deb = DebArchive::open(Path::new(XZ_DEB)).unwrap();
+    let control = deb.control().unwrap();
+    assert_eq!(control.get("Package"), Some("cpp-x86-64-linux-gnu"));
+    assert_eq!(control.get("Version"), Some("4:9.3.0-1ubuntu2"));
+    let contents = deb.contents().unwrap();
+    assert!(contents
+        .iter()
+        .any(|entry| entry.path == "./usr/share/doc/cpp-x86-64-linux-gnu/copyright"));
+}
+
+#[cfg(not(feature = "xz"))]
+#[test]
+fn xz_compressed_deb_needs_feature() {
+    let deb = DebArchive::open(Path::new(XZ_DEB)).unwrap();
+    let error = deb.control().unwrap_err();
+    assert!(error.to_string().contains("built without the \"xz\" feature"), "{}", error);
+}
@@ -0,0 +1,91 @@
+//! `info` 和 `contents` 命令的输出
+//!
+//! 使用 `sysroot/` 中的真实 `.deb`，其成员以 xz 压缩。
+
+#![cfg(feature = "xz")]
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use mini_apt::error::Error;
+use mini_apt::package::deb::DebArchive;
+
+fn fixture() -> DebArchive {
+    DebArchive::open(Path::new("sysroot/cpp-x86-64-linux-gnu_9.3.0-1ubuntu2_arm64.deb")).unwrap()
+}
+
+/// 读取端已经关闭的管道
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn info_matches_dpkg_deb() {
+    let mut out = Vec::new();
+    fixture().write_info(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines[..6],
+        [
+            " new Debian package, version 2.0.",
+            " size 3424 bytes: control archive=812 bytes.",
+            "     865 bytes,    21 lines      control",
+            "     168 bytes,     2 lines      md5sums",
+            " Package: cpp-x86-64-linux-gnu",
+            " Source: gcc-defaults (1.185.1ubuntu2)",
+        ]
+    );
+    assert!(lines.contains(&"  The GNU C preprocessor is a macro processor that is used automatically"));
+    assert!(lines.contains(&"  ."));
+    assert_eq!(lines.last(), Some(&" Maintainer scripts: (none)"));
+}
+
+#[test]
+fn contents_matches_dpkg_deb() {
+    let mut out = Vec::new();
+    fixture().write_contents(&mut out).unwrap();
+    // 时间按本地时区显示，比较时去掉日期和时间两列
+    let lines: Vec<String> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            [&fields[..3], &fields[5..]].concat().join(" ")
+        })
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "drwxr-xr-x root/root 0 ./",
+            "drwxr-xr-x root/root 0 ./usr/",
+            "drwxr-xr-x root/root 0 ./usr/bin/",
+            "drwxr-xr-x root/root 0 ./usr/share/",
+            "drwxr-xr-x root/root 0 ./usr/share/doc/",
+            "drwxr-xr-x root/root 0 ./usr/share/doc/cpp-x86-64-linux-gnu/",
+            "-rw-r--r-- root/root 849 ./usr/share/doc/cpp-x86-64-linux-gnu/changelog.Debian.gz",
+            "-rw-r--r-- root/root 2207 ./usr/share/doc/cpp-x86-64-linux-gnu/copyright",
+            "drwxr-xr-x root/root 0 ./usr/share/man/",
+            "drwxr-xr-x root/root 0 ./usr/share/man/man1/",
+            "lrwxrwxrwx root/root 0 ./usr/bin/x86_64-linux-gnu-cpp -> x86_64-linux-gnu-cpp-9",
+            "lrwxrwxrwx root/root 0 ./usr/share/man/man1/x86_64-linux-gnu-cpp.1.gz -> x86_64-linux-gnu-cpp-9.1.gz",
+        ]
+    );
+}
+
+#[test]
+fn closed_pipe_is_reported_as_io_error() {
+    let broken_pipe = |result: mini_apt::error::Result<()>| {
+        matches!(result, Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe)
+    };
+    assert!(broken_pipe(fixture().write_info(&mut ClosedPipe)));
+    assert!(broken_pipe(fixture().write_contents(&mut ClosedPipe)));
+}
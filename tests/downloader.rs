@@ -0,0 +1,57 @@
+//! 下载管理器的全局和每主机并发限制
+
+mod common;
+
+use std::time::Duration;
+
+use common::TestServer;
+use mini_apt::package::downloader::{DownloadLimits, DownloadManager};
+use mini_apt::progress::SilentObserver;
+
+/// 每个响应等待的时间，足以让同时发出的请求在服务器端重叠
+const DELAY: Duration = Duration::from_millis(200);
+
+/// 通过 `urls` 中的每个地址并发获取 `/file` 各 `count` 次，返回服务器端同时处理的最大连接数
+async fn peak(server: &TestServer, urls: &[String], count: usize, limits: DownloadLimits) -> usize {
+    server.insert("/file", "content");
+    let manager = DownloadManager::new(limits).unwrap();
+    let urls: Vec<String> = urls.iter().flat_map(|url| vec![format!("{}/file", url); count]).collect();
+    let results = futures::future::join_all(urls.iter().map(|url| manager.fetch(url, &SilentObserver))).await;
+    for result in results {
+        assert_eq!(result.unwrap(), b"content");
+    }
+    server.peak_connections()
+}
+
+#[tokio::test]
+async fn per_host_limit() {
+    let server = TestServer::start(DELAY).await;
+    let limits = DownloadLimits {
+        max_concurrent: 16,
+        max_per_host: 2,
+    };
+    assert_eq!(peak(&server, std::slice::from_ref(&server.url), 6, limits).await, 2);
+}
+
+#[tokio::test]
+async fn per_host_limit_applies_to_each_host() {
+    // 同一个服务器通过两个主机名访问，两个主机各自最多 2 个连接
+    let server = TestServer::start(DELAY).await;
+    let hosts = [server.url.clone(), server.url.replace("127.0.0.1", "localhost")];
+    let limits = DownloadLimits {
+        max_concurrent: 16,
+        max_per_host: 2,
+    };
+    assert_eq!(peak(&server, &hosts, 6, limits).await, 4);
+}
+
+#[tokio::test]
+async fn global_limit() {
+    let server = TestServer::start(DELAY).await;
+    let hosts = [server.url.clone(), server.url.replace("127.0.0.1", "localhost")];
+    let limits = DownloadLimits {
+        max_concurrent: 3,
+        max_per_host: 4,
+    };
+    assert_eq!(peak(&server, &hosts, 6, limits).await, 3);
+}
@@ -0,0 +1,108 @@
+//! `diff --ed` 补丁的应用和 PDiff 增量更新
+
+mod common;
+
+use std::io::Write;
+use std::time::Duration;
+
+use common::{sha256_hex, TestServer};
+use mini_apt::error::Error;
+use mini_apt::package::downloader::{DownloadLimits, DownloadManager};
+use mini_apt::package::pdiff::{apply_ed_script, update_index};
+use mini_apt::package::release::Release;
+use mini_apt::progress::SilentObserver;
+
+fn lines(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+fn apply(before: &str, script: &str) -> Result<Vec<String>, Error> {
+    let mut result = lines(before);
+    apply_ed_script(&mut result, script)?;
+    Ok(result)
+}
+
+#[test]
+fn append_change_and_delete() {
+    assert_eq!(apply("a b", "2a\nc\nd\n.\n").unwrap(), lines("a b c d"));
+    assert_eq!(apply("a b", "0a\nz\n.\n").unwrap(), lines("z a b"));
+    assert_eq!(apply("a b c d", "2,3c\nX\n.\n").unwrap(), lines("a X d"));
+    assert_eq!(apply("a b c d", "2,3d\n1d\n").unwrap(), lines("d"));
+    assert_eq!(apply("a b c", "3d\n1c\nA\nB\n.\n").unwrap(), lines("A B b"));
+}
+
+#[test]
+fn line_consisting_of_a_dot() {
+    // GNU diff --ed 为内容为 `.` 的行输出 `..`、`.`、`s/.//`，再用 `a` 继续追加
+    let result = apply("a b", "1a\nx\n..\n.\ns/.//\na\ny\n.\n").unwrap();
+    assert_eq!(result, ["a", "x", ".", "y", "b"]);
+    assert_eq!(apply("a b", "2c\n..\n.\ns/.//\n").unwrap(), ["a", "."]);
+
+    // `diff -e` 把 a b c 改为 a x . y b . 的实际输出
+    let script = "3c\n..\n.\ns/.//\n1a\nx\n..\n.\ns/.//\na\ny\n.\n";
+    assert_eq!(apply("a b c", script).unwrap(), ["a", "x", ".", "y", "b", "."]);
+}
+
+#[test]
+fn range_after_end_of_file() {
+    assert!(matches!(apply("a b", "3d\n"), Err(Error::Parse(_))));
+    assert!(matches!(apply("a b", "2,3c\nx\n.\n"), Err(Error::Parse(_))));
+    assert!(matches!(apply("a b", "5a\nx\n.\n"), Err(Error::Parse(_))));
+}
+
+#[test]
+fn malformed_commands() {
+    for script in ["1x\n", "d\n", "0d\n", "2,1d\n", "a,bd\n", "1é\n", "é\n", "1a\nunterminated\n", "s/.//\n"] {
+        let mut before = lines("a b");
+        if script == "s/.//\n" {
+            // 当前行不以 `.` 开头
+            before.push("c".to_string());
+        }
+        assert!(matches!(apply_ed_script(&mut before, script), Err(Error::Parse(_))), "{:?}", script);
+    }
+}
+
+#[tokio::test]
+async fn patched_index_must_match_release() {
+    let cached = "Package: a\nVersion: 1\n";
+    let patched = "Package: a\nVersion: 2\n";
+    let script = "2c\nVersion: 2\n.\n";
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(script.as_bytes()).unwrap();
+    let patch = gz.finish().unwrap();
+    let diff_index = format!(
+        "SHA256-Current: {} {}\nSHA256-History:\n {} {} T-1\nSHA256-Patches:\n {} {} T-1\n",
+        sha256_hex(patched.as_bytes()),
+        patched.len(),
+        sha256_hex(cached.as_bytes()),
+        cached.len(),
+        sha256_hex(script.as_bytes()),
+        script.len()
+    );
+
+    let server = TestServer::start(Duration::ZERO).await;
+    server.insert("/dists/focal/main/binary-arm64/Packages.diff/Index", diff_index.clone());
+    server.insert("/dists/focal/main/binary-arm64/Packages.diff/T-1.gz", patch);
+    let base = format!("{}/dists/focal", server.url);
+    let manager = DownloadManager::new(DownloadLimits::default()).unwrap();
+    let release = |packages: &str| {
+        Release::parse(&format!(
+            "Suite: focal\nSHA256:\n {} {} main/binary-arm64/Packages\n {} {} main/binary-arm64/Packages.diff/Index\n",
+            sha256_hex(packages.as_bytes()),
+            packages.len(),
+            sha256_hex(diff_index.as_bytes()),
+            diff_index.len()
+        ))
+        .unwrap()
+    };
+    let path = "main/binary-arm64/Packages";
+
+    let updated = update_index(&manager, &base, path, &release(patched), cached, &SilentObserver).await.unwrap();
+    assert_eq!(updated, patched);
+
+    // 补丁本身完好，但结果与 Release 中的校验和不一致
+    let error = update_index(&manager, &base, path, &release("Package: a\nVersion: 3\n"), cached, &SilentObserver)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Checksum { .. }), "{}", error);
+}
@@ -0,0 +1,98 @@
+//! `--dry-run` 和 `--print-uris` 的输出格式
+
+use std::path::PathBuf;
+
+use mini_apt::installer::{InstallPlan, ResolvedPackage, SkippedItem};
+use mini_apt::package::checksum::Checksum;
+use mini_apt::source::{Artifact, ArtifactLocation};
+
+fn plan() -> InstallPlan {
+    let package = |name: &str, installed_size| ResolvedPackage {
+        name: name.to_string(),
+        version: "1.0-1".to_string(),
+        architecture: "arm64".to_string(),
+        mirror: "http://primary".to_string(),
+        installed_size,
+        mark: None,
+    };
+    InstallPlan {
+        packages: vec![package("hello", Some(3072)), package("libhello", None)],
+        artifacts: vec![
+            Artifact {
+                file_name: "hello_1.0-1_arm64.deb".to_string(),
+                locations: vec![
+                    ArtifactLocation::new("http://primary/pool/h/hello_1.0-1_arm64.deb", "http://primary"),
+                    ArtifactLocation::new("http://backup/pool/h/hello_1.0-1_arm64.deb", "http://backup"),
+                ],
+                dest_dir: PathBuf::from("/sysroot/var/cache/apt/archives"),
+                checksum: Checksum::Sha256("abc123".to_string()),
+                size: Some(2048),
+            },
+            Artifact {
+                file_name: "libhello.tar".to_string(),
+                locations: vec![ArtifactLocation::new("http://primary/libhello.tar", "http://primary")],
+                dest_dir: PathBuf::from("/sysroot/downloads"),
+                checksum: Checksum::Md5("d41d8cd9".to_string()),
+                size: None,
+            },
+            // 已在本地的文件没有下载位置
+            Artifact {
+                file_name: "local.deb".to_string(),
+                locations: Vec::new(),
+                dest_dir: PathBuf::from("/tmp"),
+                checksum: Checksum::Sha256("00".to_string()),
+                size: Some(10),
+            },
+        ],
+        skipped: vec![SkippedItem {
+            name: "base".to_string(),
+            reason: "already installed".to_string(),
+        }],
+        errors: Vec::new(),
+    }
+}
+
+#[test]
+fn print_uris_format() {
+    assert_eq!(
+        plan().print_uris(),
+        [
+            "'http://primary/pool/h/hello_1.0-1_arm64.deb' hello_1.0-1_arm64.deb 2048 SHA256:abc123",
+            "'http://primary/libhello.tar' libhello.tar 0 MD5:d41d8cd9",
+        ]
+    );
+}
+
+#[test]
+fn plan_summary() {
+    assert_eq!(
+        plan().summary(),
+        [
+            "The following packages would be installed:",
+            "  hello 1.0-1 (arm64) from http://primary",
+            "  libhello 1.0-1 (arm64) from http://primary",
+            "Downloads:",
+            "  http://primary/pool/h/hello_1.0-1_arm64.deb",
+            "    (fallback) http://backup/pool/h/hello_1.0-1_arm64.deb",
+            "    2.0 KiB SHA256:abc123 -> /sysroot/var/cache/apt/archives/hello_1.0-1_arm64.deb",
+            "  http://primary/libhello.tar",
+            "    unknown size MD5:d41d8cd9 -> /sysroot/downloads/libhello.tar",
+            "    10 B SHA256:00 -> /tmp/local.deb",
+            "Skip base: already installed",
+            "Need to get 2.0 KiB of archives.",
+            "After this operation, 3.0 KiB of additional disk space will be used.",
+        ]
+    );
+}
+
+#[test]
+fn empty_plan_summary() {
+    assert_eq!(
+        InstallPlan::default().summary(),
+        [
+            "The following packages would be installed:",
+            "Need to get 0 B of archives.",
+            "After this operation, 0 B of additional disk space will be used.",
+        ]
+    );
+}
@@ -0,0 +1,70 @@
+//! `Release` 文件不可用时的处理
+
+mod common;
+
+use std::time::Duration;
+
+use common::{sha256_hex, temp_dir, TestServer};
+use mini_apt::error::Error;
+use mini_apt::package::architecture::Architecture;
+use mini_apt::package::downloader::{DownloadLimits, DownloadManager};
+use mini_apt::package::package_info::{fetch_packages_file, IndexTarget};
+use mini_apt::progress::SilentObserver;
+
+const PACKAGES: &str = "Package: hello\nVersion: 1.0\nArchitecture: arm64\n";
+
+async fn mirror() -> TestServer {
+    let server = TestServer::start(Duration::ZERO).await;
+    server.insert("/dists/focal/main/binary-arm64/Packages", PACKAGES);
+    server.insert(
+        "/dists/focal/Release",
+        format!(
+            "Suite: focal\nSHA256:\n {} {} main/binary-arm64/Packages\n",
+            sha256_hex(PACKAGES.as_bytes()),
+            PACKAGES.len()
+        ),
+    );
+    server
+}
+
+fn target(server: &TestServer, lists_dir: Option<std::path::PathBuf>) -> IndexTarget {
+    let mut target = IndexTarget::new(&server.url, "focal", &["main".to_string()], &[Architecture::Arm64]);
+    target.lists_dir = lists_dir;
+    target
+}
+
+#[tokio::test]
+async fn missing_release_is_rejected_once_cached() {
+    let server = mirror().await;
+    let lists = temp_dir("release-cached");
+    let target = target(&server, Some(lists.clone()));
+    let manager = DownloadManager::new(DownloadLimits::default()).unwrap();
+
+    let content = fetch_packages_file(&manager, &target, &SilentObserver).await.unwrap();
+    assert!(content.contains("Package: hello"));
+
+    server.remove("/dists/focal/Release");
+    let error = fetch_packages_file(&manager, &target, &SilentObserver).await.unwrap_err();
+    assert!(matches!(error, Error::InvalidRelease { .. }), "{}", error);
+    std::fs::remove_dir_all(lists).unwrap();
+}
+
+#[tokio::test]
+async fn missing_release_without_cache_falls_back() {
+    let server = mirror().await;
+    server.remove("/dists/focal/Release");
+    let manager = DownloadManager::new(DownloadLimits::default()).unwrap();
+
+    let content = fetch_packages_file(&manager, &target(&server, None), &SilentObserver).await.unwrap();
+    assert!(content.contains("Package: hello"));
+}
+
+#[tokio::test]
+async fn unparsable_release_is_rejected() {
+    let server = mirror().await;
+    server.insert("/dists/focal/Release", "Suite: focal\nSHA256:\n not-a-valid-entry\n");
+    let manager = DownloadManager::new(DownloadLimits::default()).unwrap();
+
+    let error = fetch_packages_file(&manager, &target(&server, None), &SilentObserver).await.unwrap_err();
+    assert!(matches!(error, Error::InvalidRelease { .. }), "{}", error);
+}
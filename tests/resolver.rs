@@ -0,0 +1,323 @@
+//! 依赖解析：候选项回溯、虚包、冲突、替换和 Multi-Arch
+
+use mini_apt::error::{Error, Result};
+use mini_apt::package::index::PackageIndex;
+use mini_apt::package::package_info::parse_packages_file;
+use mini_apt::package::resolver::{dependency_closure, ResolveOptions};
+
+/// 由只写了关系字段的段落构造包索引，补上下载相关的必需字段
+fn index(stanzas: &str) -> PackageIndex {
+    let content: Vec<String> = stanzas
+        .split("\n\n")
+        .filter(|stanza| !stanza.trim().is_empty())
+        .map(|stanza| format!("{}\nFilename: pool/x.deb\nSize: 1\nMD5sum: 0\nSHA256: 0\n", stanza.trim()))
+        .collect();
+    parse_packages_file(&content.join("\n"))
+}
+
+/// 解析 `root`（本机架构 arm64），返回选中的 `包名:架构 版本`
+fn resolve_with(packages: &PackageIndex, root: &str, installed: &PackageIndex, options: &ResolveOptions) -> Result<Vec<String>> {
+    let root = packages.find(root, "arm64").expect("root package");
+    let closure = dependency_closure(packages, root, installed, options)?;
+    Ok(closure
+        .iter()
+        .map(|p| format!("{}:{} {}", p.package, p.architecture, p.version))
+        .collect())
+}
+
+fn resolve(packages: &PackageIndex, root: &str) -> Result<Vec<String>> {
+    resolve_with(packages, root, &PackageIndex::new(), &ResolveOptions::new("arm64"))
+}
+
+#[test]
+fn backtracks_to_second_alternative() {
+    // 选中 a 之后 a 的依赖 x 与后面的 c 冲突，只能撤销 a 改选 b
+    let packages = index(
+        "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: a | b, c
+
+Package: a
+Version: 1
+Architecture: arm64
+Depends: x
+
+Package: x
+Version: 1
+Architecture: arm64
+Conflicts: c
+
+Package: b
+Version: 1
+Architecture: arm64
+
+Package: c
+Version: 1
+Architecture: arm64
+",
+    );
+    assert_eq!(resolve(&packages, "root").unwrap(), ["root:arm64 1", "b:arm64 1", "c:arm64 1"]);
+}
+
+#[test]
+fn versioned_dependency_through_provides() {
+    let packages = index(
+        "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: mta (>= 2)
+
+Package: old-mta
+Version: 5
+Architecture: arm64
+Provides: mta (= 1)
+
+Package: any-mta
+Version: 5
+Architecture: arm64
+Provides: mta
+
+Package: new-mta
+Version: 1
+Architecture: arm64
+Provides: mta (= 3)
+",
+    );
+    // 不带版本的 Provides 不能满足带版本的依赖
+    assert_eq!(resolve(&packages, "root").unwrap(), ["root:arm64 1", "new-mta:arm64 1"]);
+}
+
+#[test]
+fn breaks_is_versioned_conflicts_is_not() {
+    let stanzas = "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: q, r
+
+Package: q
+Version: 1
+Architecture: arm64
+Breaks: r (<< 2)
+
+Package: r
+Version: 1
+Architecture: arm64
+";
+    let error = resolve(&index(stanzas), "root").unwrap_err();
+    assert!(matches!(&error, Error::Conflict { reason, .. } if reason.contains("q Breaks: r (<< 2)")), "{}", error);
+
+    // 新版本的 r 不在 Breaks 的范围内
+    let fixed = format!("{}\nPackage: r\nVersion: 2\nArchitecture: arm64\n", stanzas);
+    assert_eq!(resolve(&index(&fixed), "root").unwrap(), ["root:arm64 1", "q:arm64 1", "r:arm64 2"]);
+
+    let conflicts = fixed.replace("Breaks: r (<< 2)", "Conflicts: r");
+    let error = resolve(&index(&conflicts), "root").unwrap_err();
+    assert!(matches!(&error, Error::Unresolvable { .. } | Error::Conflict { .. }), "{}", error);
+    assert!(error.to_string().contains("q Conflicts: r"), "{}", error);
+}
+
+#[test]
+fn replaces_allows_installing_over_conflicting_package() {
+    let installed = index(
+        "
+Package: legacy
+Version: 1
+Architecture: arm64
+",
+    );
+    let stanzas = "
+Package: modern
+Version: 1
+Architecture: arm64
+Conflicts: legacy
+Replaces: legacy
+";
+    let options = ResolveOptions::new("arm64");
+    let packages = index(stanzas);
+    assert_eq!(resolve_with(&packages, "modern", &installed, &options).unwrap(), ["modern:arm64 1"]);
+
+    // 只有 Conflicts 时已安装的包挡住了新包
+    let packages = index(&stanzas.replace("Replaces: legacy\n", ""));
+    let error = resolve_with(&packages, "modern", &installed, &options).unwrap_err();
+    assert!(error.to_string().contains("which is installed"), "{}", error);
+}
+
+#[test]
+fn multi_arch_foreign_satisfies_other_architectures() {
+    let stanzas = "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: tool
+
+Package: tool
+Version: 1
+Architecture: armhf
+Multi-Arch: foreign
+";
+    assert_eq!(resolve(&index(stanzas), "root").unwrap(), ["root:arm64 1", "tool:armhf 1"]);
+
+    let error = resolve(&index(&stanzas.replace("Multi-Arch: foreign\n", "")), "root").unwrap_err();
+    assert!(matches!(error, Error::Unsatisfied { .. }), "{}", error);
+}
+
+#[test]
+fn multi_arch_same_is_co_installable() {
+    let stanzas = "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: libc6, libc6:armhf
+
+Package: libc6
+Version: 2.31
+Architecture: arm64
+Multi-Arch: same
+
+Package: libc6
+Version: 2.31
+Architecture: armhf
+Multi-Arch: same
+";
+    assert_eq!(
+        resolve(&index(stanzas), "root").unwrap(),
+        ["root:arm64 1", "libc6:arm64 2.31", "libc6:armhf 2.31"]
+    );
+
+    let error = resolve(&index(&stanzas.replace("Multi-Arch: same\n", "")), "root").unwrap_err();
+    assert!(error.to_string().contains("Multi-Arch: same"), "{}", error);
+}
+
+#[test]
+fn unresolvable_lists_each_candidate() {
+    let packages = index(
+        "
+Package: root
+Version: 1
+Architecture: arm64
+Depends: a | b
+
+Package: a
+Version: 1
+Architecture: arm64
+Depends: missing
+
+Package: b
+Version: 2
+Architecture: arm64
+Conflicts: root
+",
+    );
+    let error = resolve(&packages, "root").unwrap_err();
+    assert!(matches!(error, Error::Unresolvable { .. }), "{}", error);
+    assert_eq!(
+        error.to_string(),
+        "Cannot satisfy root depends on a | b: \
+         a 1: Unsatisfiable dependency: a depends on missing, needed for root -> a; \
+         b 2: b:arm64 conflicts with root:arm64: b Conflicts: root"
+    );
+}
+
+#[test]
+fn gives_up_after_step_limit() {
+    // 五层、每层十个版本，最底层的依赖不存在，完整搜索需要 111111 步
+    let mut stanzas = String::from("Package: root\nVersion: 1\nArchitecture: arm64\nDepends: l1\n\n");
+    for level in 1..=5 {
+        for version in 1..=10 {
+            let next = if level == 5 { "missing".to_string() } else { format!("l{}", level + 1) };
+            stanzas.push_str(&format!(
+                "Package: l{}\nVersion: {}\nArchitecture: arm64\nDepends: {}\n\n",
+                level, version, next
+            ));
+        }
+    }
+    let error = resolve(&index(&stanzas), "root").unwrap_err();
+    assert!(error.to_string().contains("gave up after 100000 steps"), "{}", error);
+}
+
+/// 推荐 `docs`（依赖缺失）、`clash`（与 `root` 冲突）和 `helper`，建议 `manual`
+const SOFT: &str = "
+Package: root
+Version: 1
+Architecture: arm64
+Recommends: docs, clash, helper
+Suggests: manual
+
+Package: docs
+Version: 1
+Architecture: arm64
+Depends: missing
+
+Package: clash
+Version: 1
+Architecture: arm64
+Conflicts: root
+
+Package: helper
+Version: 1
+Architecture: arm64
+
+Package: manual
+Version: 1
+Architecture: arm64
+";
+
+#[test]
+fn recommends_only_when_enabled() {
+    let packages = index(SOFT);
+    let installed = PackageIndex::new();
+    assert_eq!(resolve(&packages, "root").unwrap(), ["root:arm64 1"]);
+
+    let mut options = ResolveOptions::new("arm64");
+    options.install_recommends = true;
+    // 依赖缺失和冲突的推荐包被跳过，不影响解析结果
+    assert_eq!(
+        resolve_with(&packages, "root", &installed, &options).unwrap(),
+        ["root:arm64 1", "helper:arm64 1"]
+    );
+}
+
+#[test]
+fn suggests_only_when_enabled() {
+    let packages = index(SOFT);
+    let installed = PackageIndex::new();
+    let mut options = ResolveOptions::new("arm64");
+    options.install_suggests = true;
+    assert_eq!(
+        resolve_with(&packages, "root", &installed, &options).unwrap(),
+        ["root:arm64 1", "manual:arm64 1"]
+    );
+}
+
+#[test]
+fn soft_dependencies_have_their_own_step_limit() {
+    // 必需依赖先在 x 下失败的搜索树中消耗约 8.9 万步，再由 y 满足
+    let mut stanzas = String::from(
+        "Package: root\nVersion: 1\nArchitecture: arm64\nDepends: x | y\nRecommends: r\n\n\
+         Package: x\nVersion: 1\nArchitecture: arm64\nDepends: h1\n\n\
+         Package: y\nVersion: 1\nArchitecture: arm64\n\n\
+         Package: r\nVersion: 1\nArchitecture: arm64\nDepends: s | t\n\n\
+         Package: s\nVersion: 1\nArchitecture: arm64\nDepends: m1\n\n\
+         Package: t\nVersion: 1\nArchitecture: arm64\n\n",
+    );
+    // 推荐的 r 在 s 下的失败搜索还需要约 2.3 万步，两者相加超过限制
+    for (prefix, versions) in [("h", 17), ("m", 12)] {
+        for level in 1..=4 {
+            for version in 1..=versions {
+                let next = if level == 4 { "missing".to_string() } else { format!("{}{}", prefix, level + 1) };
+                stanzas.push_str(&format!(
+                    "Package: {}{}\nVersion: {}\nArchitecture: arm64\nDepends: {}\n\n",
+                    prefix, level, version, next
+                ));
+            }
+        }
+    }
+    let mut options = ResolveOptions::new("arm64");
+    options.install_recommends = true;
+    let closure = resolve_with(&index(&stanzas), "root", &PackageIndex::new(), &options).unwrap();
+    assert_eq!(closure, ["root:arm64 1", "y:arm64 1", "r:arm64 1", "t:arm64 1"]);
+}
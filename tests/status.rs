@@ -0,0 +1,117 @@
+//! 安装到根目录：升级、删除、`Replaces` 和文件冲突
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::{build_deb, temp_dir};
+use mini_apt::error::Error;
+use mini_apt::package::deb::DebArchive;
+use mini_apt::package::status::StatusDb;
+
+fn install(dir: &Path, root: &Path, control: &str, files: &[(&str, &str)]) -> mini_apt::error::Result<u64> {
+    DebArchive::open(&build_deb(dir, control, files))?.install(root)
+}
+
+fn listed(root: &Path, name: &str) -> Vec<PathBuf> {
+    StatusDb::load(root).unwrap().files(name).unwrap()
+}
+
+#[test]
+fn upgrade_deletes_dropped_files() {
+    let dir = temp_dir("status-upgrade");
+    let root = dir.join("root");
+    let v1 = "Package: a\nVersion: 1\nArchitecture: arm64\n";
+    install(&dir, &root, v1, &[("usr/bin/a", "1"), ("usr/share/a/old", "old")]).unwrap();
+    assert!(root.join("usr/share/a/old").exists());
+
+    let v2 = "Package: a\nVersion: 2\nArchitecture: arm64\n";
+    install(&dir, &root, v2, &[("usr/bin/a", "2")]).unwrap();
+    assert!(!root.join("usr/share/a/old").exists());
+    assert!(!root.join("usr/share/a").exists());
+    assert_eq!(std::fs::read_to_string(root.join("usr/bin/a")).unwrap(), "2");
+    assert_eq!(StatusDb::load(&root).unwrap().installed_version("a"), Some("2"));
+    assert!(!listed(&root, "a").contains(&PathBuf::from("/usr/share/a/old")));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn shared_file_survives_remove() {
+    let dir = temp_dir("status-shared");
+    let root = dir.join("root");
+    let control = |arch: &str| format!("Package: libx\nVersion: 1\nArchitecture: {}\nMulti-Arch: same\n", arch);
+    for arch in ["arm64", "armhf"] {
+        let library = format!("usr/lib/{}/libx.so", arch);
+        install(&dir, &root, &control(arch), &[(&library, arch), ("usr/share/doc/libx/copyright", "c")]).unwrap();
+    }
+
+    let removed = StatusDb::update(&root, |db| db.remove("libx:armhf")).unwrap();
+    assert_eq!(removed, 1);
+    assert!(!root.join("usr/lib/armhf").exists());
+    assert!(root.join("usr/lib/arm64/libx.so").exists());
+    assert!(root.join("usr/share/doc/libx/copyright").exists());
+
+    StatusDb::update(&root, |db| db.remove("libx")).unwrap();
+    assert!(!root.join("usr/share/doc/libx/copyright").exists());
+    assert!(StatusDb::load(&root).unwrap().installed().next().is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn replaces_without_conflicts_takes_over_files() {
+    let dir = temp_dir("status-replaces");
+    let root = dir.join("root");
+    let old = "Package: tools\nVersion: 1\nArchitecture: arm64\n";
+    install(&dir, &root, old, &[("usr/bin/tool", "old"), ("usr/bin/other", "o")]).unwrap();
+
+    let new = "Package: tool\nVersion: 1\nArchitecture: arm64\nReplaces: tools (<< 2)\n";
+    install(&dir, &root, new, &[("usr/bin/tool", "new")]).unwrap();
+    // 被替换的包仍然安装，但不再拥有移交出去的文件
+    assert_eq!(listed(&root, "tools"), [PathBuf::from("/usr"), PathBuf::from("/usr/bin"), PathBuf::from("/usr/bin/other")]);
+    assert!(listed(&root, "tool").contains(&PathBuf::from("/usr/bin/tool")));
+
+    StatusDb::update(&root, |db| db.remove("tools")).unwrap();
+    assert_eq!(std::fs::read_to_string(root.join("usr/bin/tool")).unwrap(), "new");
+    assert!(!root.join("usr/bin/other").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn replaces_with_conflicts_removes_other_package() {
+    let dir = temp_dir("status-conflicts");
+    let root = dir.join("root");
+    let old = "Package: legacy\nVersion: 1\nArchitecture: arm64\n";
+    install(&dir, &root, old, &[("usr/bin/tool", "old"), ("usr/share/legacy/data", "d")]).unwrap();
+
+    let new = "Package: modern\nVersion: 1\nArchitecture: arm64\nConflicts: legacy\nReplaces: legacy\n";
+    install(&dir, &root, new, &[("usr/bin/tool", "new")]).unwrap();
+    let db = StatusDb::load(&root).unwrap();
+    assert!(db.get("legacy").is_none());
+    assert!(db.get("modern").is_some());
+    assert!(!root.join("var/lib/dpkg/info/legacy.list").exists());
+    assert!(!root.join("usr/share/legacy").exists());
+    assert_eq!(std::fs::read_to_string(root.join("usr/bin/tool")).unwrap(), "new");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn overwrite_without_replaces_is_refused() {
+    let dir = temp_dir("status-overwrite");
+    let root = dir.join("root");
+    let first = "Package: first\nVersion: 1\nArchitecture: arm64\n";
+    install(&dir, &root, first, &[("usr/bin/tool", "first")]).unwrap();
+
+    let second = "Package: second\nVersion: 1\nArchitecture: arm64\n";
+    let error = install(&dir, &root, second, &[("usr/bin/extra", "x"), ("usr/bin/tool", "second")]).unwrap_err();
+    assert!(matches!(&error, Error::Conflict { other, .. } if other == "first:arm64"), "{}", error);
+    assert!(error.to_string().contains("trying to overwrite /usr/bin/tool"), "{}", error);
+    // 检查在解包之前进行，没有写入任何文件
+    assert_eq!(std::fs::read_to_string(root.join("usr/bin/tool")).unwrap(), "first");
+    assert!(!root.join("usr/bin/extra").exists());
+    assert!(StatusDb::load(&root).unwrap().get("second").is_none());
+
+    // 版本范围之外的 Replaces 同样不允许覆盖
+    let versioned = "Package: second\nVersion: 1\nArchitecture: arm64\nReplaces: first (>= 2)\n";
+    assert!(install(&dir, &root, versioned, &[("usr/bin/tool", "second")]).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}